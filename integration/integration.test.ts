@@ -9,6 +9,7 @@ declare let process: {
     JEST_ENV: string,
     TEST_TENANT_ID_APNS: string,
     TEST_PROJECT_ID: string,
+    TEST_SCAM_PROJECT_ID: string,
   }
 }
 
@@ -23,6 +24,12 @@ const ENV = process.env.JEST_ENV;
 
 const TEST_PROJECT_ID = process.env.TEST_PROJECT_ID || 'e4eae1aad4503db9966a04fd045a7e4d'
 
+// CSRF tokens are bound to the verified domains of the project they were
+// issued for, so the scam checks require a project having the tested origins
+// (app.uniswap.org, invite-celestia.com, my-dapp.io) as verified domains.
+const TEST_SCAM_PROJECT_ID = process.env.TEST_SCAM_PROJECT_ID
+const itScam = TEST_SCAM_PROJECT_ID ? it : it.skip
+
 const BASE_URL = BASE_URLS.get(process.env.JEST_ENV)
 
 describe('verify', () => {
@@ -38,10 +45,35 @@ describe('verify', () => {
   describe('Attestation', () => {
     const url = `${BASE_URL}/attestation`
 
-    it('non-scam origin', async () => {
+    it('localhost origin', async () => {
+      let resp: any = await http.get(`${BASE_URL}/${TEST_PROJECT_ID}`)
+      let csrfToken = resp.headers["x-csrf-token"]
+
+      resp = await http.post(`${url}`, {'origin': 'http://localhost:3000', 'attestationId': 'local'}, {
+        headers: { "x-csrf-token": csrfToken },
+      })
+      expect(resp.status).toBe(200)
+
+      resp = await http.get(`${url}/local`)
+      expect(resp.status).toBe(200)
+      expect(resp.data.origin).toBe('http://localhost:3000')
+    })
+
+    it('origin not allowed for the project', async () => {
       let resp: any = await http.get(`${BASE_URL}/${TEST_PROJECT_ID}`)
       let csrfToken = resp.headers["x-csrf-token"]
 
+      resp = await http.post(`${url}`, {'origin': 'https://evil.com', 'attestationId': 'some'}, {
+        headers: { "x-csrf-token": csrfToken },
+      })
+      expect(resp.status).toBe(403)
+      expect(resp.data).toContain("Origin is not allowed")
+    })
+
+    itScam('non-scam origin', async () => {
+      let resp: any = await http.get(`${BASE_URL}/${TEST_SCAM_PROJECT_ID}`)
+      let csrfToken = resp.headers["x-csrf-token"]
+
       resp = await http.post(`${url}`, {'origin': 'http://app.uniswap.org', 'attestationId': 'some'}, {
         headers: { "x-csrf-token": csrfToken },
       })
@@ -58,8 +90,8 @@ describe('verify', () => {
       expect(resp.headers["access-control-allow-origin"]).toBe("*")
     })
     
-    it('scam origin', async () => {
-      let resp: any = await http.get(`${BASE_URL}/${TEST_PROJECT_ID}`)
+    itScam('scam origin', async () => {
+      let resp: any = await http.get(`${BASE_URL}/${TEST_SCAM_PROJECT_ID}`)
       let csrfToken = resp.headers["x-csrf-token"]
 
       resp = await http.post(`${url}`, {'origin': 'https://invite-celestia.com', 'attestationId': 'evil'}, {
@@ -71,8 +103,8 @@ describe('verify', () => {
       expect(resp.data.isScam).toBe(true)
     })
 
-    it('scam: unknown', async () => {
-      let resp: any = await http.get(`${BASE_URL}/${TEST_PROJECT_ID}`)
+    itScam('scam: unknown', async () => {
+      let resp: any = await http.get(`${BASE_URL}/${TEST_SCAM_PROJECT_ID}`)
       let csrfToken = resp.headers["x-csrf-token"]
 
       resp = await http.post(`${url}`, {'origin': 'https://my-dapp.io', 'attestationId': 'tbd'}, {
@@ -103,6 +135,29 @@ describe('verify', () => {
     })
   })
 
+  describe('Token refresh', () => {
+    const url = `${BASE_URL}/token/refresh`
+
+    it('exchanges a valid token', async () => {
+      let resp: any = await http.get(`${BASE_URL}/${TEST_PROJECT_ID}`)
+      let csrfToken = resp.headers["x-csrf-token"]
+
+      resp = await http.post(url, null, { headers: { "x-csrf-token": csrfToken } })
+      expect(resp.status).toBe(200)
+      expect(resp.data.token).toBe(resp.headers["x-csrf-token"])
+
+      resp = await http.post(`${BASE_URL}/attestation`, {'origin': 'http://localhost', 'attestationId': 'some'}, {
+        headers: { "x-csrf-token": resp.data.token },
+      })
+      expect(resp.status).toBe(200)
+    })
+
+    it('rejects an invalid token', async () => {
+      let resp = await http.post(url, null, { headers: { "x-csrf-token": 'aaaaaaaaaaaa' } })
+      expect(resp.status).toBe(403)
+    })
+  })
+
   describe('Enclave', () => {
     const url = `${BASE_URL}`
 
@@ -112,7 +167,16 @@ describe('verify', () => {
       expect(resp.status).toBe(200)
 
       let policy = resp.headers["content-security-policy"]
-      expect(policy).toBe(`frame-ancestors http://*.localhost http://localhost https://localhost:* http://localhost:* https://*.localhost:* http://*.localhost:* https://127.0.0.1:* http://127.0.0.1:*`)
+      expect(policy).toMatch(/^default-src 'none'; script-src 'nonce-[0-9a-f]{32}'; connect-src 'self'; base-uri 'none'; form-action 'none'; /)
+      expect(policy).toContain(`frame-ancestors http://*.localhost http://localhost https://localhost:* http://localhost:* https://*.localhost:* http://*.localhost:* https://127.0.0.1:* http://127.0.0.1:*`)
+
+      let nonce = policy.match(/'nonce-([0-9a-f]{32})'/)[1]
+      expect(resp.data).toContain(`nonce="${nonce}"`)
+
+      expect(resp.headers["x-content-type-options"]).toBe("nosniff")
+      expect(resp.headers["referrer-policy"]).toBe("no-referrer")
+      expect(resp.headers["cross-origin-resource-policy"]).toBe("cross-origin")
+      expect(resp.headers["cache-control"]).toBe("no-store")
     })
 
     describe('invalid project ID', () => {
@@ -145,7 +209,18 @@ describe('verify', () => {
       expect(resp.status).toBe(200)
 
       let policy = resp.headers["content-security-policy"]
-      expect(policy).toBe(undefined)
+      expect(policy).toBe(`default-src 'none'; frame-ancestors 'none'`)
+      expect(resp.headers["x-verify-status"]).toMatch(/^(disabled|no_verified_domains)$/)
+      expect(resp.data).toBe('')
+    })
+
+    it('project with Verify disabled, explained via JSON', async () => {
+      let resp = await http.get(`${url}/0eae574067d750bb4a5e84bb99b5845f`, {
+        headers: { Accept: 'application/json' },
+      })
+      expect(resp.status).toBe(200)
+      expect(resp.data.status).toBe(resp.headers["x-verify-status"])
+      expect(resp.data.message).toContain('Verify API')
     })
   })
   describe('index.js', () => {
@@ -154,14 +229,37 @@ describe('verify', () => {
     it('get index.js', async () => {
       let resp: any = await http.get(`${BASE_URL}/${TEST_PROJECT_ID}`)
       let csrfToken = resp.headers["x-csrf-token"]
+      expect(resp.data).toContain(`<meta name="csrf-token" content="${csrfToken}">`)
 
-      resp = await http.get(`${url}/index.js?token=${csrfToken}`)
+      let [, version, integrity] = resp.data.match(/src="\/index\.js\?protocol=1&amp;v=([0-9a-f]+)" integrity="([^"]+)"/)
+      expect(integrity).toMatch(/^sha256-/)
+
+      resp = await http.get(`${url}/index.js?protocol=1&v=${version}`)
       expect(resp.status).toBe(200)
+      expect(resp.headers["content-type"]).toBe("text/javascript; charset=utf-8")
+      expect(resp.headers["x-content-type-options"]).toBe("nosniff")
+      expect(resp.headers["cache-control"]).toBe("public, max-age=31536000, immutable")
     })
 
-    it('doesn\'t allow invalid `token` parameters', async () => {
-      let resp = await http.get(`${url}/index.js?token=eyJ0eXAiOiJKV1QiLCJhbGciOiJIUzI1NiJ9.<img src onerror=alert(document.domain)>`)
+    it('serves the requested protocol version', async () => {
+      let resp: any = await http.get(`${BASE_URL}/${TEST_PROJECT_ID}?protocol=2`)
+      let [, version] = resp.data.match(/src="\/index\.js\?protocol=2&amp;v=([0-9a-f]+)"/)
+
+      resp = await http.get(`${url}/index.js?protocol=2&v=${version}`)
+      expect(resp.status).toBe(200)
+      expect(resp.data).toContain('"verify_attestation_result"')
+      expect(resp.headers["cache-control"]).toBe("public, max-age=31536000, immutable")
+    })
+
+    it('rejects unsupported protocol versions', async () => {
+      let resp = await http.get(`${BASE_URL}/${TEST_PROJECT_ID}?protocol=3`)
       expect(resp.status).toBe(400)
     })
+
+    it('doesn\'t cache unknown versions', async () => {
+      let resp = await http.get(`${url}/index.js?v=0000000000000000`)
+      expect(resp.status).toBe(200)
+      expect(resp.headers["cache-control"]).toBe("no-cache")
+    })
   })
 })
@@ -0,0 +1,10 @@
+-- Timestamps are unix seconds, as the same schema is used by SQLite and Postgres.
+CREATE TABLE attestations (
+    id TEXT PRIMARY KEY,
+    origin TEXT NOT NULL,
+    created_at BIGINT NOT NULL,
+    expires_at BIGINT NOT NULL
+);
+
+CREATE INDEX attestations_origin_created_at_idx ON attestations (origin, created_at);
+CREATE INDEX attestations_created_at_idx ON attestations (created_at);
@@ -0,0 +1,197 @@
+use {
+    super::{AttestationStore, Result, ScanAttestations, Ttl},
+    std::time::Duration,
+    tokio::time::{interval, Interval, MissedTickBehavior},
+    tracing::{info, warn},
+};
+
+/// Options of copying attestations between stores.
+#[derive(Clone, Copy, Debug)]
+pub struct Options {
+    /// Only scan the source store, without writing anything.
+    pub dry_run: bool,
+
+    /// Maximum number of attestations processed per second, `0` means
+    /// unlimited.
+    pub rate_limit: u32,
+
+    /// Number of attestations requested from the source store at once.
+    pub batch_size: usize,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CopyReport {
+    pub scanned: usize,
+    pub copied: usize,
+    pub failed: usize,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct VerifyReport {
+    pub checked: usize,
+    pub matching: usize,
+    pub missing: usize,
+    pub mismatched: usize,
+    pub failed: usize,
+}
+
+fn rate_limiter(rate_limit: u32) -> Option<Interval> {
+    (rate_limit > 0).then(|| {
+        // Rates above 1e9 would make the period zero, which `interval` panics on.
+        let period = (Duration::from_secs(1) / rate_limit).max(Duration::from_nanos(1));
+        let mut interval = interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        interval
+    })
+}
+
+/// Copies every attestation of the `source` store into the `destination` one,
+/// preserving their TTLs.
+///
+/// Failing to scan the `source` aborts the copy, while failed writes are only
+/// counted.
+pub async fn copy(
+    source: &impl ScanAttestations,
+    destination: &impl AttestationStore,
+    opts: Options,
+) -> Result<CopyReport> {
+    let mut report = CopyReport::default();
+    let mut rate_limiter = rate_limiter(opts.rate_limit);
+    let mut cursor = 0;
+
+    loop {
+        let (next, batch) = source.scan_attestations(cursor, opts.batch_size).await?;
+
+        for a in batch {
+            report.scanned += 1;
+
+            if opts.dry_run {
+                continue;
+            }
+
+            if let Some(rate_limiter) = &mut rate_limiter {
+                rate_limiter.tick().await;
+            }
+
+            match destination
+                .set_attestation_with_ttl(&a.id, &a.origin, a.ttl.map_or(Ttl::Never, Ttl::After))
+                .await
+            {
+                Ok(()) => report.copied += 1,
+                Err(e) => {
+                    warn!(id = %a.id, "Failed to copy attestation: {e:?}");
+                    report.failed += 1;
+                }
+            }
+        }
+
+        info!(?report, "Copying attestations");
+
+        cursor = next;
+        if cursor == 0 {
+            return Ok(report);
+        }
+    }
+}
+
+/// Checks that every attestation of the `source` store is present in the
+/// `destination` one.
+pub async fn verify(
+    source: &impl ScanAttestations,
+    destination: &impl AttestationStore,
+    opts: Options,
+) -> Result<VerifyReport> {
+    let mut report = VerifyReport::default();
+    let mut rate_limiter = rate_limiter(opts.rate_limit);
+    let mut cursor = 0;
+
+    loop {
+        let (next, batch) = source.scan_attestations(cursor, opts.batch_size).await?;
+
+        for a in batch {
+            if let Some(rate_limiter) = &mut rate_limiter {
+                rate_limiter.tick().await;
+            }
+
+            report.checked += 1;
+            match destination.get_attestation(&a.id).await {
+                Ok(Some(origin)) if origin == a.origin => report.matching += 1,
+                Ok(Some(origin)) => {
+                    warn!(id = %a.id, expected = %a.origin, got = %origin, "Attestation mismatch");
+                    report.mismatched += 1;
+                }
+                Ok(None) => report.missing += 1,
+                Err(e) => {
+                    warn!(id = %a.id, "Failed to verify attestation: {e:?}");
+                    report.failed += 1;
+                }
+            }
+        }
+
+        info!(?report, "Verifying attestations");
+
+        cursor = next;
+        if cursor == 0 {
+            return Ok(report);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_copy_and_verify() {
+    use super::memory::Memory;
+
+    let source = Memory::default();
+    for i in 0..5 {
+        source
+            .set_attestation(&i.to_string(), "https://walletconnect.com")
+            .await
+            .unwrap();
+    }
+
+    let opts = Options {
+        dry_run: true,
+        rate_limit: 0,
+        batch_size: 2,
+    };
+
+    let destination = Memory::default();
+    destination
+        .set_attestation("0", "https://evil.com")
+        .await
+        .unwrap();
+
+    let report = copy(&source, &destination, opts).await.unwrap();
+    assert_eq!(report, CopyReport {
+        scanned: 5,
+        ..Default::default()
+    });
+
+    let report = verify(&source, &destination, opts).await.unwrap();
+    assert_eq!(report, VerifyReport {
+        checked: 5,
+        mismatched: 1,
+        missing: 4,
+        ..Default::default()
+    });
+
+    // Rates too high for a non-zero tick period are clamped.
+    let opts = Options {
+        dry_run: false,
+        rate_limit: u32::MAX,
+        ..opts
+    };
+    let report = copy(&source, &destination, opts).await.unwrap();
+    assert_eq!(report, CopyReport {
+        scanned: 5,
+        copied: 5,
+        failed: 0,
+    });
+
+    let report = verify(&source, &destination, opts).await.unwrap();
+    assert_eq!(report, VerifyReport {
+        checked: 5,
+        matching: 5,
+        ..Default::default()
+    });
+}
@@ -1,57 +1,95 @@
 use {
-    super::{AttestationStore, Result},
-    crate::http_server::{CsrfToken, TokenManager},
+    super::{AttestationStore, Result, Ttl},
+    crate::util::{
+        http::{self, Idempotency},
+        signer::RequestSigner,
+        time::unix_timestamp,
+    },
     async_trait::async_trait,
-    hyper::StatusCode,
+    hyper::{Method, StatusCode},
     reqwest::Url,
     serde::Serialize,
-    std::time::Duration,
 };
 
 #[derive(Clone)]
 pub struct CloudflareKv {
     pub endpoint: Url,
-    pub token_manager: TokenManager,
-    pub http_client: reqwest::Client,
+    pub signer: RequestSigner,
+    pub http_client: http::Client,
 }
 
 impl CloudflareKv {
-    pub fn new(endpoint: Url, token_manager: TokenManager) -> Self {
+    pub fn new(endpoint: Url, signer: RequestSigner, http_config: http::Config) -> Self {
         Self {
             endpoint,
-            token_manager,
-            http_client: reqwest::Client::new(),
+            signer,
+            http_client: http::Client::new("cf_kv", http_config),
         }
     }
+
+    /// Sends a signed request. Both reading and writing an attestation are
+    /// idempotent, so every request may be retried.
+    async fn send(&self, method: Method, url: Url, body: Vec<u8>) -> Result<reqwest::Response> {
+        let resp = self
+            .http_client
+            .send(Idempotency::Idempotent, |client| {
+                // Signing every attempt, so retries don't get rejected as expired.
+                let headers = self
+                    .signer
+                    .sign(&method, url.path(), &body, unix_timestamp());
+
+                let req = client.request(method.clone(), url.clone()).headers(headers);
+
+                if body.is_empty() {
+                    req
+                } else {
+                    req.header(hyper::header::CONTENT_TYPE, "application/json")
+                        .body(body.clone())
+                }
+            })
+            .await?;
+
+        Ok(resp)
+    }
 }
 
+/// Minimum `expiration_ttl` accepted by Cloudflare KV.
+const MIN_EXPIRATION_TTL_SECS: u64 = 60;
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct SetAttestationCompatBody<'a> {
     attestation_id: &'a str,
     origin: &'a str,
+
+    /// KV `expiration_ttl` of the attestation, the default of the worker if
+    /// not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expiration_ttl: Option<u64>,
 }
 
 #[async_trait]
 impl AttestationStore for CloudflareKv {
     async fn set_attestation(&self, id: &str, origin: &str) -> Result<()> {
+        self.set_attestation_with_ttl(id, origin, Ttl::Default)
+            .await
+    }
+
+    async fn set_attestation_with_ttl(&self, id: &str, origin: &str, ttl: Ttl) -> Result<()> {
+        // The worker always sets an expiration, so never expiring attestations
+        // get its default one.
+        let expiration_ttl = match ttl {
+            Ttl::After(ttl) => Some(ttl.as_secs().max(MIN_EXPIRATION_TTL_SECS)),
+            Ttl::Default | Ttl::Never => None,
+        };
+
         let url = self.endpoint.join("/attestation")?;
-        let res = self
-            .http_client
-            .post(url)
-            .header(
-                CsrfToken::header_name(),
-                self.token_manager
-                    .generate_csrf_token()
-                    .map_err(|e| anyhow::anyhow!("{e:?}"))?,
-            )
-            .json(&SetAttestationCompatBody {
-                attestation_id: id,
-                origin,
-            })
-            .timeout(Duration::from_secs(1))
-            .send()
-            .await?;
+        let body = serde_json::to_vec(&SetAttestationCompatBody {
+            attestation_id: id,
+            origin,
+            expiration_ttl,
+        })?;
+        let res = self.send(Method::POST, url, body).await?;
         if res.status().is_success() {
             Ok(())
         } else {
@@ -67,12 +105,7 @@ impl AttestationStore for CloudflareKv {
         let url = self
             .endpoint
             .join(&format!("/v1/compat-attestation/{id}"))?;
-        let response = self
-            .http_client
-            .get(url)
-            .timeout(Duration::from_secs(1))
-            .send()
-            .await?;
+        let response = self.send(Method::GET, url, Vec::new()).await?;
         match response.status() {
             status if status.is_success() => {
                 let value = response.text().await?;
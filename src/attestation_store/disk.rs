@@ -0,0 +1,188 @@
+use {
+    super::{AttestationStore, Result, Ttl},
+    crate::util::time::unix_timestamp,
+    anyhow::Context as _,
+    async_trait::async_trait,
+    metrics::counter,
+    redb::{Database, TableDefinition},
+    serde::Deserialize,
+    std::{path::PathBuf, sync::Arc, time::Duration},
+    tracing::{info, warn},
+};
+
+/// Attestation id -> (origin, unix timestamp of the expiration).
+const ATTESTATIONS: TableDefinition<&str, (&str, u64)> = TableDefinition::new("attestations");
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Path to the database file, created if missing.
+    pub path: PathBuf,
+
+    pub ttl_secs: u64,
+
+    /// How often the expired attestations are deleted.
+    pub sweep_interval_secs: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            path: "attestations.redb".into(),
+            ttl_secs: 300,
+            sweep_interval_secs: 60,
+        }
+    }
+}
+
+/// [`AttestationStore`] backed by an embedded database on the local disk.
+///
+/// Every write is committed durably before it's acknowledged, so attestations
+/// survive crashes.
+#[derive(Clone)]
+pub struct Disk {
+    db: Arc<Database>,
+    ttl: Duration,
+}
+
+impl Disk {
+    pub fn new(config: &Config) -> Result<Self> {
+        let db = Database::create(&config.path)
+            .with_context(|| format!("Failed to open {}", config.path.display()))?;
+
+        // Read transactions fail if the table doesn't exist yet.
+        let tx = db.begin_write()?;
+        tx.open_table(ATTESTATIONS)?;
+        tx.commit()?;
+
+        Ok(Self {
+            db: Arc::new(db),
+            ttl: Duration::from_secs(config.ttl_secs),
+        })
+    }
+
+    /// Runs a blocking database operation on the blocking thread pool.
+    async fn blocking<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&Database) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || f(&db)).await?
+    }
+
+    /// Deletes the expired attestations, returning their number.
+    pub async fn sweep(&self) -> Result<u64> {
+        self.blocking(|db| {
+            let now = unix_timestamp();
+            let mut deleted = 0;
+
+            let tx = db.begin_write()?;
+            tx.open_table(ATTESTATIONS)?.retain(|_, (_, expires_at)| {
+                let keep = expires_at > now;
+                deleted += u64::from(!keep);
+                keep
+            })?;
+            tx.commit()?;
+
+            Ok(deleted)
+        })
+        .await
+    }
+
+    /// Periodically deletes the expired attestations.
+    pub async fn run_sweeper(self, interval: Duration) {
+        let mut interval = tokio::time::interval(interval);
+
+        loop {
+            interval.tick().await;
+
+            match self.sweep().await {
+                Ok(deleted) => {
+                    counter!("attestation_store_disk_swept", deleted);
+                    info!(deleted, "Expired attestations swept");
+                }
+                Err(e) => {
+                    counter!("attestation_store_disk_sweep_errors", 1);
+                    warn!("Failed to sweep attestations: {e:?}");
+                }
+            }
+        }
+    }
+
+    async fn set(&self, id: &str, origin: &str, ttl: Ttl) -> Result<()> {
+        let (id, origin) = (id.to_owned(), origin.to_owned());
+        let expires_at = ttl
+            .or(self.ttl)
+            .map_or(u64::MAX, |ttl| unix_timestamp() + ttl.as_secs());
+
+        self.blocking(move |db| {
+            let tx = db.begin_write()?;
+            tx.open_table(ATTESTATIONS)?
+                .insert(id.as_str(), (origin.as_str(), expires_at))?;
+            tx.commit()?;
+            Ok(())
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl AttestationStore for Disk {
+    async fn set_attestation(&self, id: &str, origin: &str) -> Result<()> {
+        self.set(id, origin, Ttl::Default).await
+    }
+
+    async fn get_attestation(&self, id: &str) -> Result<Option<String>> {
+        let id = id.to_owned();
+
+        self.blocking(move |db| {
+            let tx = db.begin_read()?;
+            let Some(guard) = tx.open_table(ATTESTATIONS)?.get(id.as_str())? else {
+                return Ok(None);
+            };
+
+            // Expired attestations are served as missing until they are swept.
+            let (origin, expires_at) = guard.value();
+            Ok((expires_at > unix_timestamp()).then(|| origin.to_owned()))
+        })
+        .await
+    }
+
+    async fn set_attestation_with_ttl(&self, id: &str, origin: &str, ttl: Ttl) -> Result<()> {
+        self.set(id, origin, ttl).await
+    }
+}
+
+#[tokio::test]
+async fn test_disk() {
+    let path = std::env::temp_dir().join(format!("bouncer-test-{}.redb", rand::random::<u64>()));
+    let config = Config {
+        path: path.clone(),
+        ..Default::default()
+    };
+
+    let store = Disk::new(&config).unwrap();
+    store.set_attestation("a", "https://a.com").await.unwrap();
+    store
+        .set_attestation_with_ttl("b", "https://b.com", Ttl::After(Duration::ZERO))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store.get_attestation("a").await.unwrap().as_deref(),
+        Some("https://a.com")
+    );
+    assert_eq!(store.get_attestation("b").await.unwrap(), None);
+    assert_eq!(store.sweep().await.unwrap(), 1);
+
+    // Attestations survive reopening the database.
+    drop(store);
+    let store = Disk::new(&config).unwrap();
+    assert_eq!(
+        store.get_attestation("a").await.unwrap().as_deref(),
+        Some("https://a.com")
+    );
+
+    drop(store);
+    let _ = std::fs::remove_file(path);
+}
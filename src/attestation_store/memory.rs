@@ -0,0 +1,122 @@
+use {
+    super::{AttestationStore, Result, ScanAttestations, StoredAttestation, Ttl},
+    async_trait::async_trait,
+    std::{
+        collections::BTreeMap,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Mutex,
+        },
+        time::{Duration, Instant},
+    },
+};
+
+/// [`AttestationStore`] keeping attestations in process memory, meant for
+/// development and tests.
+///
+/// Attestations are lost on restart and aren't shared between instances.
+pub struct Memory {
+    /// Attestation id -> (origin, expiration), `None` if it never expires.
+    data: Mutex<BTreeMap<String, (String, Option<Instant>)>>,
+    ttl: Duration,
+    is_down: AtomicBool,
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(300))
+    }
+}
+
+impl Memory {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            data: Mutex::default(),
+            ttl,
+            is_down: AtomicBool::new(false),
+        }
+    }
+
+    /// Makes every operation fail while `is_down`, simulating an outage of
+    /// the store.
+    pub fn set_down(&self, is_down: bool) {
+        self.is_down.store(is_down, Ordering::Relaxed);
+    }
+
+    fn check_up(&self) -> Result<()> {
+        if self.is_down.load(Ordering::Relaxed) {
+            return Err(anyhow::anyhow!("Memory store is down"));
+        }
+
+        Ok(())
+    }
+}
+
+fn is_expired(expires_at: Option<Instant>, now: Instant) -> bool {
+    expires_at.is_some_and(|at| at <= now)
+}
+
+#[async_trait]
+impl AttestationStore for Memory {
+    async fn set_attestation(&self, id: &str, origin: &str) -> Result<()> {
+        self.set_attestation_with_ttl(id, origin, Ttl::Default)
+            .await
+    }
+
+    async fn get_attestation(&self, id: &str) -> Result<Option<String>> {
+        self.check_up()?;
+
+        let data = self.data.lock().unwrap();
+        Ok(data
+            .get(id)
+            .filter(|(_, expires_at)| !is_expired(*expires_at, Instant::now()))
+            .map(|(origin, _)| origin.clone()))
+    }
+
+    async fn set_attestation_with_ttl(&self, id: &str, origin: &str, ttl: Ttl) -> Result<()> {
+        self.check_up()?;
+
+        let now = Instant::now();
+        let mut data = self.data.lock().unwrap();
+
+        // Dropping the expired attestations on every write keeps the memory
+        // bounded without a background task.
+        data.retain(|_, (_, expires_at)| !is_expired(*expires_at, now));
+        data.insert(
+            id.to_owned(),
+            (origin.to_owned(), ttl.or(self.ttl).map(|ttl| now + ttl)),
+        );
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ScanAttestations for Memory {
+    async fn scan_attestations(
+        &self,
+        cursor: u64,
+        count: usize,
+    ) -> Result<(u64, Vec<StoredAttestation>)> {
+        self.check_up()?;
+
+        let now = Instant::now();
+        let data = self.data.lock().unwrap();
+        let batch: Vec<_> = data
+            .iter()
+            .skip(cursor as usize)
+            .take(count)
+            .filter(|(_, (_, expires_at))| !is_expired(*expires_at, now))
+            .map(|(id, (origin, expires_at))| StoredAttestation {
+                id: id.clone(),
+                origin: origin.clone(),
+                ttl: expires_at.map(|at| at.saturating_duration_since(now)),
+            })
+            .collect();
+
+        let next = cursor as usize + count;
+        let next = if next >= data.len() { 0 } else { next as u64 };
+
+        Ok((next, batch))
+    }
+}
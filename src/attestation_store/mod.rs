@@ -1,13 +1,90 @@
+pub mod backfill;
 pub mod cf_kv;
-pub mod migration;
+pub mod disk;
+pub mod memory;
 pub mod redis;
+pub mod replicated;
+pub mod sql;
 
-use async_trait::async_trait;
+use {async_trait::async_trait, std::time::Duration};
 
 #[async_trait]
 pub trait AttestationStore: Send + Sync + 'static {
     async fn set_attestation(&self, id: &str, origin: &str) -> Result<()>;
     async fn get_attestation(&self, id: &str) -> Result<Option<String>>;
+
+    /// Sets an attestation expiring after the provided `ttl`, see [`Ttl`].
+    ///
+    /// Stores not supporting custom TTLs ignore it.
+    async fn set_attestation_with_ttl(&self, id: &str, origin: &str, _ttl: Ttl) -> Result<()> {
+        self.set_attestation(id, origin).await
+    }
+}
+
+#[async_trait]
+impl<T: AttestationStore + ?Sized> AttestationStore for Box<T> {
+    async fn set_attestation(&self, id: &str, origin: &str) -> Result<()> {
+        (**self).set_attestation(id, origin).await
+    }
+
+    async fn get_attestation(&self, id: &str) -> Result<Option<String>> {
+        (**self).get_attestation(id).await
+    }
+
+    async fn set_attestation_with_ttl(&self, id: &str, origin: &str, ttl: Ttl) -> Result<()> {
+        (**self).set_attestation_with_ttl(id, origin, ttl).await
+    }
+}
+
+/// Time to live of an attestation being set.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Ttl {
+    /// Default TTL of the store.
+    #[default]
+    Default,
+
+    /// Expires after the duration.
+    After(Duration),
+
+    /// Never expires, for copying the attestations not expiring in their
+    /// source store.
+    Never,
+}
+
+impl Ttl {
+    /// Resolves the TTL against the `default` one of a store, `None` meaning
+    /// the attestation never expires.
+    pub fn or(self, default: Duration) -> Option<Duration> {
+        match self {
+            Self::Default => Some(default),
+            Self::After(ttl) => Some(ttl),
+            Self::Never => None,
+        }
+    }
+}
+
+/// Attestation read via [`ScanAttestations`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StoredAttestation {
+    pub id: String,
+    pub origin: String,
+
+    /// Remaining time to live, `None` if the attestation never expires.
+    pub ttl: Option<Duration>,
+}
+
+/// [`AttestationStore`] which attestations can be iterated over.
+#[async_trait]
+pub trait ScanAttestations: AttestationStore {
+    /// Returns a batch of roughly `count` attestations starting at the
+    /// `cursor`, and the cursor of the next batch.
+    ///
+    /// The iteration starts and ends with the cursor being `0`.
+    async fn scan_attestations(
+        &self,
+        cursor: u64,
+        count: usize,
+    ) -> Result<(u64, Vec<StoredAttestation>)>;
 }
 
 pub type Error = anyhow::Error;
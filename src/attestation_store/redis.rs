@@ -1,7 +1,8 @@
 use {
-    super::{AttestationStore, Result},
+    super::{AttestationStore, Result, ScanAttestations, StoredAttestation, Ttl},
     crate::util::redis,
     async_trait::async_trait,
+    std::time::Duration,
 };
 
 const ATTESTATION_TTL_SECS: usize = 300;
@@ -9,10 +10,64 @@ const ATTESTATION_TTL_SECS: usize = 300;
 #[async_trait]
 impl AttestationStore for redis::Adapter {
     async fn set_attestation(&self, id: &str, origin: &str) -> Result<()> {
-        self.set_ex(id, origin, ATTESTATION_TTL_SECS).await
+        self.set_attestation_with_ttl(id, origin, Ttl::Default)
+            .await
     }
 
     async fn get_attestation(&self, id: &str) -> Result<Option<String>> {
         self.get(id).await
     }
+
+    async fn set_attestation_with_ttl(&self, id: &str, origin: &str, ttl: Ttl) -> Result<()> {
+        match ttl.or(Duration::from_secs(ATTESTATION_TTL_SECS as u64)) {
+            // SETEX doesn't accept zero.
+            Some(ttl) => {
+                self.set_ex(id, origin, (ttl.as_secs() as usize).max(1))
+                    .await
+            }
+            None => self.set(id, origin).await,
+        }
+    }
+}
+
+#[async_trait]
+impl ScanAttestations for redis::Adapter {
+    async fn scan_attestations(
+        &self,
+        cursor: u64,
+        count: usize,
+    ) -> Result<(u64, Vec<StoredAttestation>)> {
+        let (cursor, keys) = self.scan(cursor, count).await?;
+
+        let mut attestations = Vec::with_capacity(keys.len());
+        for id in keys {
+            // The key may have expired in the meantime.
+            if let Some((origin, ttl)) = self.get_with_ttl(&id).await? {
+                attestations.push(StoredAttestation { id, origin, ttl });
+            }
+        }
+
+        Ok((cursor, attestations))
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires Redis, e.g. `docker compose up redis`"]
+async fn test_default_ttl() {
+    let url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379/0".into());
+    let store = redis::new("test", url).unwrap();
+    let id = format!("test-{}", rand::random::<u64>());
+
+    store
+        .set_attestation_with_ttl(&id, "https://a.com", Ttl::Default)
+        .await
+        .unwrap();
+    let (_, ttl) = store.get_with_ttl::<_, String>(&id).await.unwrap().unwrap();
+    let ttl = ttl.unwrap();
+    assert!(ttl > Duration::from_secs(290) && ttl <= Duration::from_secs(300));
+
+    store.set_attestation(&id, "https://a.com").await.unwrap();
+    let (_, ttl) = store.get_with_ttl::<_, String>(&id).await.unwrap().unwrap();
+    let ttl = ttl.unwrap();
+    assert!(ttl > Duration::from_secs(290) && ttl <= Duration::from_secs(300));
 }
@@ -0,0 +1,396 @@
+use {
+    super::{AttestationStore, Result, Ttl},
+    async_trait::async_trait,
+    futures::future::{self, Either},
+    metrics::counter,
+    serde::Deserialize,
+    std::sync::Arc,
+    tap::TapFallible as _,
+    tracing::{error, warn},
+};
+
+/// How writes are replicated across the legs of a [`Replicated`] store.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WritePolicy {
+    /// Both legs are written concurrently and both have to succeed.
+    BothMustSucceed,
+
+    /// Both legs are written concurrently, but only the primary one has to
+    /// succeed.
+    #[default]
+    PrimaryOnly,
+
+    /// Only the primary leg is awaited, the secondary one is written in the
+    /// background.
+    AsyncMirror,
+}
+
+/// Which leg of a [`Replicated`] store is read from.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadPolicy {
+    /// The secondary leg is only read if the primary one misses or fails.
+    #[default]
+    PrimaryThenFallback,
+
+    /// Both legs are read concurrently, the first hit wins.
+    Race,
+
+    /// The primary leg is only read if the secondary one misses or fails.
+    SecondaryFirst,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Policy {
+    pub write_policy: WritePolicy,
+    pub read_policy: ReadPolicy,
+
+    /// Share of reads (from `0.0` to `1.0`) being shadowed by reading both
+    /// legs in the background and comparing their results.
+    pub shadow_read_rate: f64,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Leg {
+    Primary,
+    Secondary,
+}
+
+impl Leg {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Primary => "primary",
+            Self::Secondary => "secondary",
+        }
+    }
+}
+
+/// [`AttestationStore`] replicating attestations across two stores.
+///
+/// Hits of the secondary leg are written back into the primary one (read
+/// repair).
+pub struct Replicated<P, S> {
+    primary: Arc<P>,
+    secondary: Arc<S>,
+    policy: Policy,
+}
+
+impl<P, S> Replicated<P, S> {
+    pub fn new(primary: P, secondary: S, policy: Policy) -> Self {
+        Self {
+            primary: Arc::new(primary),
+            secondary: Arc::new(secondary),
+            policy,
+        }
+    }
+}
+
+impl<P, S> Replicated<P, S>
+where
+    P: AttestationStore,
+    S: AttestationStore,
+{
+    async fn set(&self, leg: Leg, id: &str, origin: &str, ttl: Ttl) -> Result<()> {
+        match leg {
+            Leg::Primary => set(&*self.primary, leg, id, origin, ttl).await,
+            Leg::Secondary => set(&*self.secondary, leg, id, origin, ttl).await,
+        }
+    }
+
+    async fn get(&self, leg: Leg, id: &str) -> Result<Option<String>> {
+        let res = match leg {
+            Leg::Primary => self.primary.get_attestation(id).await,
+            Leg::Secondary => self.secondary.get_attestation(id).await,
+        };
+
+        let outcome = match &res {
+            Ok(Some(_)) => "hit",
+            Ok(None) => "miss",
+            Err(_) => "error",
+        };
+        counter!("attestation_store_reads", 1, "leg" => leg.as_str(), "outcome" => outcome);
+
+        res.tap_err(|e| {
+            error!(
+                leg = leg.as_str(),
+                "AttestationStore::get_attestation: {e:?}"
+            )
+        })
+    }
+
+    /// Writes the attestation found in the secondary leg back into the
+    /// primary one, without blocking the read.
+    fn read_repair(&self, id: &str, origin: &str) {
+        let primary = self.primary.clone();
+        let (id, origin) = (id.to_owned(), origin.to_owned());
+
+        tokio::spawn(async move {
+            let res = primary.set_attestation(&id, &origin).await;
+            let outcome = if res.is_ok() { "ok" } else { "error" };
+            counter!("attestation_store_read_repairs", 1, "outcome" => outcome);
+            let _ = res.tap_err(|e| warn!("Read repair failed: {e:?}"));
+        });
+    }
+
+    /// Reads both legs in the background and reports whether they agree.
+    ///
+    /// Issued alongside the served read, so the legs are observed before any
+    /// read repair caused by it.
+    fn shadow_read(&self, id: &str) {
+        let (primary, secondary) = (self.primary.clone(), self.secondary.clone());
+        let id = id.to_owned();
+
+        tokio::spawn(async move {
+            let (p, s) = tokio::join!(primary.get_attestation(&id), secondary.get_attestation(&id));
+
+            let outcome = shadow_read_outcome(&p, &s);
+            counter!("attestation_store_shadow_reads", 1, "outcome" => outcome);
+
+            if !matches!(outcome, "match" | "both_missing") {
+                warn!(
+                    id,
+                    outcome,
+                    primary = ?p.as_ref().map_err(ToString::to_string),
+                    secondary = ?s.as_ref().map_err(ToString::to_string),
+                    "Attestation store legs disagree"
+                );
+            }
+        });
+    }
+
+    /// Falls back to the `second` leg unless the `first` one hit.
+    ///
+    /// If both legs miss or fail, the result of the `first` one is returned.
+    async fn get_with_fallback(
+        &self,
+        first: Leg,
+        first_res: Result<Option<String>>,
+        id: &str,
+    ) -> Result<Option<String>> {
+        if let Ok(Some(origin)) = first_res {
+            return Ok(Some(origin));
+        }
+
+        let second = match first {
+            Leg::Primary => Leg::Secondary,
+            Leg::Secondary => Leg::Primary,
+        };
+
+        match self.get(second, id).await {
+            Ok(Some(origin)) => {
+                if second == Leg::Secondary {
+                    self.read_repair(id, &origin);
+                }
+                Ok(Some(origin))
+            }
+            Ok(None) | Err(_) => first_res,
+        }
+    }
+}
+
+fn shadow_read_outcome(
+    primary: &Result<Option<String>>,
+    secondary: &Result<Option<String>>,
+) -> &'static str {
+    match (primary, secondary) {
+        (Ok(Some(p)), Ok(Some(s))) if p == s => "match",
+        (Ok(None), Ok(None)) => "both_missing",
+        (Ok(Some(_)), Ok(Some(_))) => "mismatch",
+        (Ok(None), Ok(Some(_))) => "missing_primary",
+        (Ok(Some(_)), Ok(None)) => "missing_secondary",
+        (Err(_), _) | (_, Err(_)) => "error",
+    }
+}
+
+async fn set(
+    store: &impl AttestationStore,
+    leg: Leg,
+    id: &str,
+    origin: &str,
+    ttl: Ttl,
+) -> Result<()> {
+    let res = store.set_attestation_with_ttl(id, origin, ttl).await;
+
+    let outcome = if res.is_ok() { "ok" } else { "error" };
+    counter!("attestation_store_writes", 1, "leg" => leg.as_str(), "outcome" => outcome);
+
+    res.tap_err(|e| {
+        error!(
+            leg = leg.as_str(),
+            "AttestationStore::set_attestation: {e:?}"
+        )
+    })
+}
+
+#[async_trait]
+impl<P, S> AttestationStore for Replicated<P, S>
+where
+    P: AttestationStore,
+    S: AttestationStore,
+{
+    async fn set_attestation(&self, id: &str, origin: &str) -> Result<()> {
+        self.set_attestation_with_ttl(id, origin, Ttl::Default)
+            .await
+    }
+
+    async fn set_attestation_with_ttl(&self, id: &str, origin: &str, ttl: Ttl) -> Result<()> {
+        match self.policy.write_policy {
+            WritePolicy::BothMustSucceed => {
+                let (primary, secondary) = tokio::join!(
+                    self.set(Leg::Primary, id, origin, ttl),
+                    self.set(Leg::Secondary, id, origin, ttl)
+                );
+                primary.and(secondary)
+            }
+            WritePolicy::PrimaryOnly => {
+                let (primary, _) = tokio::join!(
+                    self.set(Leg::Primary, id, origin, ttl),
+                    self.set(Leg::Secondary, id, origin, ttl)
+                );
+                primary
+            }
+            WritePolicy::AsyncMirror => {
+                let secondary = self.secondary.clone();
+                let (id_, origin_) = (id.to_owned(), origin.to_owned());
+
+                tokio::spawn(async move {
+                    let _ = set(&*secondary, Leg::Secondary, &id_, &origin_, ttl).await;
+                });
+
+                self.set(Leg::Primary, id, origin, ttl).await
+            }
+        }
+    }
+
+    async fn get_attestation(&self, id: &str) -> Result<Option<String>> {
+        if self.policy.shadow_read_rate > 0.0
+            && rand::random::<f64>() < self.policy.shadow_read_rate
+        {
+            self.shadow_read(id);
+        }
+
+        match self.policy.read_policy {
+            ReadPolicy::PrimaryThenFallback => {
+                let res = self.get(Leg::Primary, id).await;
+                self.get_with_fallback(Leg::Primary, res, id).await
+            }
+            ReadPolicy::SecondaryFirst => {
+                let res = self.get(Leg::Secondary, id).await;
+                self.get_with_fallback(Leg::Secondary, res, id).await
+            }
+            ReadPolicy::Race => {
+                let primary = Box::pin(self.get(Leg::Primary, id));
+                let secondary = Box::pin(self.get(Leg::Secondary, id));
+
+                let (first, first_res, rest) = match future::select(primary, secondary).await {
+                    Either::Left((res, rest)) => (Leg::Primary, res, Either::Left(rest)),
+                    Either::Right((res, rest)) => (Leg::Secondary, res, Either::Right(rest)),
+                };
+
+                if let Ok(Some(origin)) = first_res {
+                    if first == Leg::Secondary {
+                        self.read_repair(id, &origin);
+                    }
+                    return Ok(Some(origin));
+                }
+
+                match rest.await {
+                    Ok(Some(origin)) => {
+                        if first == Leg::Primary {
+                            self.read_repair(id, &origin);
+                        }
+                        Ok(Some(origin))
+                    }
+                    Ok(None) | Err(_) => first_res,
+                }
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_write_policies() {
+    use super::memory::Memory;
+
+    let down = || {
+        let store = Memory::default();
+        store.set_down(true);
+        store
+    };
+
+    let policy = |write_policy| Policy {
+        write_policy,
+        ..Default::default()
+    };
+
+    let store = Replicated::new(Memory::default(), down(), policy(WritePolicy::PrimaryOnly));
+    assert!(store.set_attestation("a", "b").await.is_ok());
+
+    let store = Replicated::new(Memory::default(), down(), policy(WritePolicy::AsyncMirror));
+    assert!(store.set_attestation("a", "b").await.is_ok());
+
+    let store = Replicated::new(
+        Memory::default(),
+        down(),
+        policy(WritePolicy::BothMustSucceed),
+    );
+    assert!(store.set_attestation("a", "b").await.is_err());
+}
+
+#[tokio::test]
+async fn test_read_policies() {
+    use super::memory::Memory;
+
+    for read_policy in [
+        ReadPolicy::PrimaryThenFallback,
+        ReadPolicy::Race,
+        ReadPolicy::SecondaryFirst,
+    ] {
+        let secondary = Memory::default();
+        secondary.set_attestation("a", "b").await.unwrap();
+
+        let store = Replicated::new(Memory::default(), secondary, Policy {
+            read_policy,
+            ..Default::default()
+        });
+
+        assert_eq!(
+            store.get_attestation("a").await.unwrap().as_deref(),
+            Some("b")
+        );
+        assert_eq!(store.get_attestation("c").await.unwrap(), None);
+
+        if read_policy != ReadPolicy::SecondaryFirst {
+            // Read repair is done in the background.
+            tokio::task::yield_now().await;
+            assert_eq!(
+                store.primary.get_attestation("a").await.unwrap().as_deref(),
+                Some("b"),
+                "{read_policy:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_shadow_read_outcome() {
+    let some = |s: &str| Ok(Some(s.to_owned()));
+
+    assert_eq!(shadow_read_outcome(&some("a"), &some("a")), "match");
+    assert_eq!(shadow_read_outcome(&some("a"), &some("b")), "mismatch");
+    assert_eq!(shadow_read_outcome(&Ok(None), &Ok(None)), "both_missing");
+    assert_eq!(
+        shadow_read_outcome(&Ok(None), &some("a")),
+        "missing_primary"
+    );
+    assert_eq!(
+        shadow_read_outcome(&some("a"), &Ok(None)),
+        "missing_secondary"
+    );
+    assert_eq!(
+        shadow_read_outcome(&some("a"), &Err(anyhow::anyhow!("down"))),
+        "error"
+    );
+}
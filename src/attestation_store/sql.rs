@@ -0,0 +1,262 @@
+use {
+    super::{AttestationStore, Result, Ttl},
+    crate::util::time::unix_timestamp,
+    anyhow::Context as _,
+    async_trait::async_trait,
+    metrics::counter,
+    serde::Deserialize,
+    sqlx::{any::AnyPoolOptions, AnyPool, Row as _},
+    std::time::Duration,
+    tap::TapFallible as _,
+    tracing::{info, warn},
+};
+
+/// Migrations of the [`Sql`] store, embedded into the binary.
+static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!();
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// `sqlite://` or `postgres://` URL of the database.
+    pub url: String,
+
+    pub max_connections: u32,
+
+    /// For how long an attestation is served.
+    pub ttl_secs: u64,
+
+    /// For how long an attestation is kept for investigations after being
+    /// created.
+    pub retention_secs: u64,
+
+    /// How often the attestations past the retention period are deleted.
+    pub sweep_interval_secs: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            url: "sqlite://attestations.db?mode=rwc".into(),
+            max_connections: 10,
+            ttl_secs: 300,
+            retention_secs: 60 * 60 * 24 * 30,
+            sweep_interval_secs: 60,
+        }
+    }
+}
+
+/// Attestation as stored in a [`Sql`] store.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AttestationRecord {
+    pub id: String,
+    pub origin: String,
+
+    /// Unix timestamp of when the attestation was set.
+    pub created_at: i64,
+}
+
+/// [`AttestationStore`] backed by SQLite or Postgres.
+///
+/// Unlike the other stores, attestations are kept (but not served) after
+/// they expire, until the retention period is over.
+#[derive(Clone)]
+pub struct Sql {
+    pool: AnyPool,
+    ttl: Duration,
+}
+
+impl Sql {
+    /// Connects to the database and applies pending migrations.
+    pub async fn new(config: &Config) -> Result<Self> {
+        sqlx::any::install_default_drivers();
+
+        let pool = AnyPoolOptions::new()
+            .max_connections(config.max_connections)
+            .connect(&config.url)
+            .await
+            .context("Failed to connect to the attestation database")?;
+
+        MIGRATOR
+            .run(&pool)
+            .await
+            .context("Failed to migrate the attestation database")?;
+
+        Ok(Self {
+            pool,
+            ttl: Duration::from_secs(config.ttl_secs),
+        })
+    }
+
+    /// Deletes attestations created more than `retention` ago, returning the
+    /// number of deleted attestations.
+    pub async fn sweep(&self, retention: Duration) -> Result<u64> {
+        self.delete_created_before(now() - retention.as_secs() as i64)
+            .await
+    }
+
+    async fn delete_created_before(&self, timestamp: i64) -> Result<u64> {
+        let res = sqlx::query("DELETE FROM attestations WHERE created_at < $1")
+            .bind(timestamp)
+            .execute(&self.pool)
+            .await
+            .context("Failed to sweep attestations")?;
+
+        Ok(res.rows_affected())
+    }
+
+    /// Periodically deletes the attestations past the retention period.
+    pub async fn run_sweeper(self, config: Config) {
+        let retention = Duration::from_secs(config.retention_secs);
+        let mut interval = tokio::time::interval(Duration::from_secs(config.sweep_interval_secs));
+
+        loop {
+            interval.tick().await;
+
+            match self.sweep(retention).await {
+                Ok(deleted) => {
+                    counter!("attestation_store_sql_swept", deleted);
+                    info!(deleted, "Expired attestations swept");
+                }
+                Err(e) => {
+                    counter!("attestation_store_sql_sweep_errors", 1);
+                    warn!("{e:?}");
+                }
+            }
+        }
+    }
+
+    /// Returns the attestations of an `origin` created since the provided
+    /// unix timestamp, newest first.
+    pub async fn attestations_by_origin(
+        &self,
+        origin: &str,
+        since: i64,
+    ) -> Result<Vec<AttestationRecord>> {
+        sqlx::query(
+            "SELECT id, origin, created_at FROM attestations WHERE origin = $1 AND created_at >= \
+             $2 ORDER BY created_at DESC",
+        )
+        .bind(origin)
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to query attestations by origin")?
+        .into_iter()
+        .map(|row| {
+            Ok(AttestationRecord {
+                id: row.try_get("id")?,
+                origin: row.try_get("origin")?,
+                created_at: row.try_get("created_at")?,
+            })
+        })
+        .collect()
+    }
+
+    /// Inserts the attestation, replacing an existing one like the other
+    /// stores do.
+    async fn set(&self, id: &str, origin: &str, ttl: Ttl) -> Result<()> {
+        let now = now();
+        let expires_at = ttl
+            .or(self.ttl)
+            .map_or(i64::MAX, |ttl| now + ttl.as_secs() as i64);
+
+        sqlx::query(
+            "INSERT INTO attestations (id, origin, created_at, expires_at) VALUES ($1, $2, $3, \
+             $4) ON CONFLICT (id) DO UPDATE SET origin = excluded.origin, created_at = \
+             excluded.created_at, expires_at = excluded.expires_at",
+        )
+        .bind(id)
+        .bind(origin)
+        .bind(now)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await
+        .context("Failed to insert attestation")
+        .tap_err(|_| counter!("attestation_store_sql_write_errors", 1))?;
+
+        Ok(())
+    }
+}
+
+fn now() -> i64 {
+    unix_timestamp() as i64
+}
+
+#[async_trait]
+impl AttestationStore for Sql {
+    async fn set_attestation(&self, id: &str, origin: &str) -> Result<()> {
+        self.set(id, origin, Ttl::Default).await
+    }
+
+    async fn get_attestation(&self, id: &str) -> Result<Option<String>> {
+        sqlx::query("SELECT origin FROM attestations WHERE id = $1 AND expires_at > $2")
+            .bind(id)
+            .bind(now())
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to select attestation")
+            .tap_err(|_| counter!("attestation_store_sql_read_errors", 1))?
+            .map(|row| row.try_get("origin"))
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    async fn set_attestation_with_ttl(&self, id: &str, origin: &str, ttl: Ttl) -> Result<()> {
+        self.set(id, origin, ttl).await
+    }
+}
+
+#[tokio::test]
+async fn test_sql() {
+    let store = Sql::new(&Config {
+        // Every connection gets its own in-memory database.
+        url: "sqlite::memory:".into(),
+        max_connections: 1,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    store.set_attestation("a", "https://a.com").await.unwrap();
+    store
+        .set_attestation_with_ttl("b", "https://a.com", Ttl::After(Duration::ZERO))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store.get_attestation("a").await.unwrap().as_deref(),
+        Some("https://a.com")
+    );
+    assert_eq!(store.get_attestation("b").await.unwrap(), None);
+    assert_eq!(store.get_attestation("c").await.unwrap(), None);
+
+    // Expired attestations are still available for investigations.
+    let records = store
+        .attestations_by_origin("https://a.com", 0)
+        .await
+        .unwrap();
+    assert_eq!(records.len(), 2);
+
+    // Setting the same id again replaces the attestation.
+    store
+        .set_attestation_with_ttl("b", "https://b.com", Ttl::After(Duration::from_secs(60)))
+        .await
+        .unwrap();
+    assert_eq!(
+        store.get_attestation("b").await.unwrap().as_deref(),
+        Some("https://b.com")
+    );
+    let records = store
+        .attestations_by_origin("https://a.com", 0)
+        .await
+        .unwrap();
+    assert_eq!(records.len(), 1);
+
+    assert_eq!(store.sweep(Duration::from_secs(60)).await.unwrap(), 0);
+    assert_eq!(store.delete_created_before(now() + 1).await.unwrap(), 2);
+    assert!(store
+        .attestations_by_origin("https://a.com", 0)
+        .await
+        .unwrap()
+        .is_empty());
+}
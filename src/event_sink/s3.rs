@@ -25,7 +25,6 @@ use {
     },
 };
 
-const ANALYTICS_EXPORT_TIMEOUT: Duration = Duration::from_secs(30);
 const DATA_QUEUE_CAPACITY: usize = 8192;
 
 #[derive(Clone, Copy)]
@@ -143,6 +142,7 @@ where
 pub async fn requests_dir<Record>(
     s3_client: S3Client,
     export_bucket: String,
+    export_timeout: Duration,
 ) -> Result<Adapter<Record>>
 where
     Record: Send + Sync + 'static,
@@ -152,6 +152,7 @@ where
         data_kind: DataKind,
         s3_client: S3Client,
         export_bucket: String,
+        export_timeout: Duration,
         node_addr: IpAddr,
     ) -> ArcCollector<T>
     where
@@ -172,7 +173,7 @@ where
                 file_extension: "parquet".to_owned(),
                 bucket_name: export_bucket.to_owned(),
                 s3_client,
-                upload_timeout: ANALYTICS_EXPORT_TIMEOUT,
+                upload_timeout: export_timeout,
             })
             .with_observer(observer),
         )
@@ -184,6 +185,7 @@ where
         DataKind::Requests,
         s3_client.clone(),
         export_bucket,
+        export_timeout,
         // Used only to build a file name
         // TODO: Change it in the `wc` repo as it's not really needed for all services
         Ipv4Addr::LOCALHOST.into(),
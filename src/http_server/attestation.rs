@@ -1,17 +1,20 @@
 use {
-    super::{Command, RequestInfo, State},
+    super::{enabled_project, Command, RequestInfo, RequestKind, State},
     crate::{
         GetAttestation,
         GetAttestationResult,
+        GetVerifyStatus,
+        GetVerifyStatusResult,
         Handle,
         IsScam,
         SetAttestation,
+        SetAttestationError,
         SetAttestationResult,
     },
     axum::{
         extract::{Json, Path},
         http::StatusCode,
-        response::IntoResponse,
+        response::{IntoResponse, Response},
     },
     hyper::{header, HeaderMap},
     serde::{Deserialize, Serialize},
@@ -61,19 +64,37 @@ pub(super) async fn post<S, G>(
     headers: HeaderMap,
     request_info: RequestInfo,
     body: Json<Body>,
-) -> Result<impl IntoResponse, StatusCode>
+) -> Result<impl IntoResponse, Response>
 where
-    S: for<'a> Handle<Command<SetAttestation<'a>>, Result = SetAttestationResult>,
+    S: for<'a> Handle<Command<GetVerifyStatus<'a>>, Result = GetVerifyStatusResult>
+        + for<'a> Handle<Command<SetAttestation<'a>>, Result = SetAttestationResult>,
 {
-    s.token_manager.validate_csrf_token(&headers)?;
+    let token = s
+        .token_manager
+        .decode_csrf_token(&headers)
+        .map_err(IntoResponse::into_response)?;
+
+    let (verified_domains, config) =
+        enabled_project(&s, &token, request_info.clone(), RequestKind::Attestation).await?;
+
+    token
+        .check_origin(&body.origin, &verified_domains, &config)
+        .map_err(IntoResponse::into_response)?;
 
     let cmd = SetAttestation {
         id: &body.attestation_id,
         origin: &body.origin,
+        ttl: config.attestation_ttl(),
+        scam_check: config.scam_check,
     };
 
     s.handle(cmd, request_info)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+        .map_err(|e| match e {
+            SetAttestationError::ScamOrigin => {
+                (StatusCode::FORBIDDEN, e.to_string()).into_response()
+            }
+            SetAttestationError::Other(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        })
         .map(|_| (StatusCode::OK, "OK".to_string()))
 }
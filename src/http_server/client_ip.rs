@@ -0,0 +1,190 @@
+use {
+    axum::{
+        extract::{ConnectInfo, Request, State},
+        middleware::Next,
+        response::Response,
+    },
+    hyper::{http::HeaderName, HeaderMap},
+    ipnet::IpNet,
+    serde::{Deserialize, Deserializer},
+    std::{
+        net::{IpAddr, SocketAddr},
+        sync::Arc,
+    },
+};
+
+/// How the IP of the client is determined.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Strategy {
+    /// Address of the peer connected to the server, for deployments without a
+    /// proxy.
+    ConnectInfo,
+
+    /// `CloudFront-Viewer-Address` header, only to be used if the server is
+    /// reachable via CloudFront only.
+    Cloudfront,
+
+    /// Rightmost address of the `X-Forwarded-For` chain (followed by the peer
+    /// address) not belonging to a trusted proxy.
+    #[default]
+    RightmostUntrusted,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub strategy: Strategy,
+
+    /// CIDRs of the proxies trusted to append to `X-Forwarded-For`, private
+    /// and loopback networks by default.
+    #[serde(deserialize_with = "deserialize_cidrs")]
+    pub trusted_proxies: Vec<IpNet>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let trusted_proxies = [
+            "10.0.0.0/8",
+            "172.16.0.0/12",
+            "192.168.0.0/16",
+            "127.0.0.0/8",
+            "fc00::/7",
+            "::1/128",
+        ];
+
+        Self {
+            strategy: Strategy::default(),
+            trusted_proxies: trusted_proxies.iter().map(|s| s.parse().unwrap()).collect(),
+        }
+    }
+}
+
+fn deserialize_cidrs<'de, D>(de: D) -> Result<Vec<IpNet>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::Error as _;
+
+    Vec::<String>::deserialize(de)?
+        .iter()
+        .map(|s| {
+            s.parse()
+                .map_err(|e| D::Error::custom(format!("Invalid CIDR {s}: {e}")))
+        })
+        .collect()
+}
+
+// Using const value instead of a fn produces this warning:
+// https://rust-lang.github.io/rust-clippy/master/index.html#declare_interior_mutable_const
+const fn cloudfront_viewer_address() -> HeaderName {
+    HeaderName::from_static("cloudfront-viewer-address")
+}
+
+const fn x_forwarded_for() -> HeaderName {
+    HeaderName::from_static("x-forwarded-for")
+}
+
+impl Config {
+    /// Determines the IP of the client from the request headers and the peer
+    /// address.
+    fn client_ip(&self, headers: &HeaderMap, peer: Option<IpAddr>) -> Option<IpAddr> {
+        match self.strategy {
+            Strategy::ConnectInfo => peer,
+            Strategy::Cloudfront => {
+                // `<ip>:<port>`, where IPv6 addresses aren't bracketed.
+                let value = headers.get(cloudfront_viewer_address())?.to_str().ok()?;
+                value.rsplit_once(':')?.0.parse().ok()
+            }
+            Strategy::RightmostUntrusted => {
+                let forwarded = headers
+                    .get_all(x_forwarded_for())
+                    .iter()
+                    .filter_map(|v| v.to_str().ok())
+                    .flat_map(|v| v.split(','))
+                    .map(|ip| ip.trim().parse::<IpAddr>().ok());
+
+                let mut leftmost = None;
+                for ip in forwarded.chain(peer.map(Some)).rev() {
+                    // Trusted proxies always append valid addresses.
+                    let ip = ip?;
+                    if !self.trusted_proxies.iter().any(|net| net.contains(&ip)) {
+                        return Some(ip);
+                    }
+                    leftmost = Some(ip);
+                }
+
+                // The request originates from a trusted network.
+                leftmost
+            }
+        }
+    }
+}
+
+/// IP of the client, determined according to the [`Config`] and added to the
+/// extensions of every request having one.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ClientIp(pub IpAddr);
+
+pub(super) async fn middleware(
+    State(config): State<Arc<Config>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.ip());
+
+    if let Some(ip) = config.client_ip(req.headers(), peer) {
+        req.extensions_mut().insert(ClientIp(ip));
+    }
+
+    next.run(req).await
+}
+
+#[test]
+fn test_client_ip() {
+    fn case(config: &Config, xff: &[&str], peer: &str) -> Option<String> {
+        let mut headers = HeaderMap::new();
+        for value in xff {
+            headers.append(x_forwarded_for(), value.parse().unwrap());
+        }
+        headers.insert(
+            cloudfront_viewer_address(),
+            "2001:db8::1:46532".parse().unwrap(),
+        );
+
+        config
+            .client_ip(&headers, peer.parse().ok())
+            .map(|ip| ip.to_string())
+    }
+
+    let config = Config::default();
+    let ip = |s: &str| Some(s.to_owned());
+
+    // Spoofed entries preceding the one appended by a trusted proxy are ignored.
+    assert_eq!(
+        case(&config, &["1.1.1.1, 2.2.2.2"], "10.0.0.1"),
+        ip("2.2.2.2")
+    );
+    assert_eq!(
+        case(&config, &["1.1.1.1", "2.2.2.2, 10.0.0.2"], "10.0.0.1"),
+        ip("2.2.2.2")
+    );
+    assert_eq!(case(&config, &["1.1.1.1"], "3.3.3.3"), ip("3.3.3.3"));
+    assert_eq!(case(&config, &["junk, 10.0.0.2"], "10.0.0.1"), None);
+    assert_eq!(case(&config, &["10.0.0.3"], "10.0.0.1"), ip("10.0.0.3"));
+
+    let config = Config {
+        strategy: Strategy::Cloudfront,
+        ..Default::default()
+    };
+    assert_eq!(case(&config, &["1.1.1.1"], "10.0.0.1"), ip("2001:db8::1"));
+
+    let config = Config {
+        strategy: Strategy::ConnectInfo,
+        ..Default::default()
+    };
+    assert_eq!(case(&config, &["1.1.1.1"], "10.0.0.1"), ip("10.0.0.1"));
+}
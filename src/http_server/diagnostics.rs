@@ -0,0 +1,207 @@
+use {
+    super::{
+        build_content_security_header,
+        security_headers::{self, DEFAULT_CONTENT_SECURITY_POLICY},
+        Command,
+        RequestInfo,
+        RequestKind,
+        State,
+    },
+    crate::{
+        util::time::unix_timestamp,
+        Domain,
+        GetVerifyStatus,
+        GetVerifyStatusResult,
+        Handle,
+        ProjectId,
+        VerifyConfig,
+        VerifyStatus,
+    },
+    axum::{
+        extract::{Json, Path},
+        response::Response,
+    },
+    serde::Serialize,
+    tracing::instrument,
+};
+
+/// Placeholder of the per-response nonce in the reported
+/// `Content-Security-Policy`.
+const NONCE_PLACEHOLDER: &str = "{nonce}";
+
+/// Verify diagnostics of a project, telling its integrators why the enclave
+/// is (not) served the way they expect.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct Body {
+    project_id: ProjectId,
+    status: &'static str,
+    message: &'static str,
+    verified_domains: Vec<String>,
+
+    /// `Content-Security-Policy` the enclave would be served with, having
+    /// [`NONCE_PLACEHOLDER`] in place of the nonce.
+    content_security_policy: String,
+
+    /// Age of the project data, if it's known when it was fetched from the
+    /// registry.
+    cache_age_secs: Option<u64>,
+
+    warnings: Vec<String>,
+}
+
+#[instrument(level = "debug", skip(s))]
+pub(super) async fn get<S, G>(
+    s: State<S, G>,
+    Path(project_id): Path<ProjectId>,
+    request_info: RequestInfo,
+) -> Result<Json<Body>, Response>
+where
+    S: for<'a> Handle<Command<GetVerifyStatus<'a>>, Result = GetVerifyStatusResult>,
+{
+    let location = request_info.location.clone();
+    let cmd = GetVerifyStatus {
+        project_id: &project_id,
+        location: location.as_ref(),
+    };
+
+    let request_info = RequestInfo {
+        kind: RequestKind::Diagnostics,
+        ..request_info
+    };
+
+    let project = s.handle(cmd, request_info).await?;
+    let cache_age_secs = project
+        .fetched_at
+        .map(|at| unix_timestamp().saturating_sub(at));
+
+    let (status, message) = (project.status.as_str(), project.status.description());
+    let content_security_policy = match &project.status {
+        VerifyStatus::Enabled {
+            verified_domains,
+            config,
+        } => {
+            let frame_ancestors = build_content_security_header(verified_domains, config);
+            security_headers::enclave_content_security_policy(NONCE_PLACEHOLDER, &frame_ancestors)
+        }
+        _ => DEFAULT_CONTENT_SECURITY_POLICY.to_owned(),
+    };
+
+    // Reported whatever the status, so the domains can be fixed before Verify
+    // gets enabled.
+    let (verified_domains, warnings) = check_domains(&project.verified_domains, &project.config);
+
+    Ok(Json(Body {
+        project_id,
+        status,
+        message,
+        verified_domains,
+        content_security_policy,
+        cache_age_secs,
+        warnings,
+    }))
+}
+
+/// Normalizes the verified domains of a project the way they are enforced,
+/// see [`Domain::normalized`], returning them along with the warnings about
+/// the ones unlikely to work as intended.
+fn check_domains(domains: &[Domain], config: &VerifyConfig) -> (Vec<String>, Vec<String>) {
+    let mut warnings = vec![];
+
+    if !config.allowed_schemes.iter().any(|s| s == "https") {
+        warnings.push("HTTPS isn't an allowed scheme, HTTPS origins won't be verified".to_owned());
+    }
+
+    let mut normalized: Vec<String> = vec![];
+    for domain in domains {
+        let raw: &String = domain.as_ref();
+        let lowercase = raw.trim().to_lowercase();
+
+        let unprefixed = if let Some(rest) = lowercase.strip_prefix("http://") {
+            warnings.push(format!("{raw}: HTTP scheme, only HTTPS origins are secure"));
+            rest
+        } else if let Some(rest) = lowercase.strip_prefix("https://") {
+            warnings.push(format!(
+                "{raw}: domains should be specified without a scheme"
+            ));
+            rest
+        } else {
+            &lowercase
+        };
+
+        if let Some(rest) = unprefixed.strip_prefix("*.") {
+            warnings.push(format!(
+                "{raw}: wildcards are implied, subdomains of {rest} are always verified"
+            ));
+        }
+
+        let Some(domain) = domain.normalized() else {
+            warnings.push(format!(
+                "{raw}: malformed domain, it will never be verified"
+            ));
+            continue;
+        };
+
+        let domain: String = domain.as_ref().clone();
+        if normalized.contains(&domain) {
+            warnings.push(format!("{raw}: listed more than once"));
+            continue;
+        }
+
+        normalized.push(domain);
+    }
+
+    for domain in &normalized {
+        let parent = normalized.iter().find(|parent| {
+            domain
+                .strip_suffix(parent.as_str())
+                .is_some_and(|s| s.ends_with('.'))
+        });
+
+        if let Some(parent) = parent {
+            warnings.push(format!("{domain}: already covered by {parent}"));
+        }
+    }
+
+    (normalized, warnings)
+}
+
+#[test]
+fn test_check_domains() {
+    let domains: Vec<_> = [
+        "my-dapp.io",
+        "HTTPS://App.My-Dapp.io/",
+        "*.other.io",
+        "http://other.io",
+        "my dapp.io",
+    ]
+    .into_iter()
+    .map(|s| Domain::from(s.to_owned()))
+    .collect();
+
+    let (normalized, warnings) = check_domains(&domains, &VerifyConfig::default());
+    assert_eq!(normalized, ["my-dapp.io", "app.my-dapp.io", "other.io"]);
+    assert_eq!(warnings, [
+        "HTTPS://App.My-Dapp.io/: domains should be specified without a scheme",
+        "*.other.io: wildcards are implied, subdomains of other.io are always verified",
+        "http://other.io: HTTP scheme, only HTTPS origins are secure",
+        "http://other.io: listed more than once",
+        "my dapp.io: malformed domain, it will never be verified",
+        "app.my-dapp.io: already covered by my-dapp.io",
+    ]);
+
+    // The domains are enforced the way they are reported.
+    let enforced: Vec<_> = domains.iter().filter_map(Domain::normalized).collect();
+    assert!(super::is_origin_allowed(
+        "https://app.my-dapp.io",
+        &enforced[1..2],
+        &VerifyConfig::default()
+    ));
+
+    let config = VerifyConfig {
+        allowed_schemes: vec!["http".to_owned()],
+        ..Default::default()
+    };
+    let (_, warnings) = check_domains(&[], &config);
+    assert_eq!(warnings.len(), 1);
+}
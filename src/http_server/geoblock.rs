@@ -0,0 +1,135 @@
+use {
+    super::{client_ip::ClientIp, Server},
+    crate::Location,
+    axum::{
+        extract::{MatchedPath, Request, State},
+        middleware::Next,
+        response::{IntoResponse, Response},
+    },
+    hyper::StatusCode,
+    metrics::counter,
+    serde::Deserialize,
+    std::sync::Arc,
+    wc::geoip,
+};
+
+/// What happens to the requests which location can't be determined, e.g.
+/// because the GeoIP database isn't loaded.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Unavailable {
+    #[default]
+    FailOpen,
+    FailClosed,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Blocked regions, either ISO 3166-1 alpha-2 country codes or ISO 3166-2
+    /// subdivision codes, see [`Location::is_in`].
+    pub blocked_regions: Vec<String>,
+
+    /// Routes served everywhere, `/health` and `/ready` by default.
+    pub exempt_routes: Vec<String>,
+
+    pub unavailable: Unavailable,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            blocked_regions: vec![],
+            exempt_routes: vec!["/health".into(), "/ready".into()],
+            unavailable: Unavailable::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Checks whether a request to the `route` from the `location` is
+    /// blocked.
+    fn is_blocked(&self, route: Option<&str>, location: Option<&Location>) -> bool {
+        if self.blocked_regions.is_empty()
+            || route.is_some_and(|route| self.exempt_routes.iter().any(|r| r == route))
+        {
+            return false;
+        }
+
+        match location {
+            Some(location) => self.blocked_regions.iter().any(|r| location.is_in(r)),
+            None => self.unavailable == Unavailable::FailClosed,
+        }
+    }
+}
+
+/// Looks up the [`Location`] of the [`ClientIp`].
+fn locate<G: geoip::Resolver>(resolver: Option<&G>, ip: Option<&ClientIp>) -> Option<Location> {
+    let data = resolver?.lookup_geo_data(ip?.0).ok()?;
+
+    Some(Location {
+        country: data.country?,
+        subdivisions: data.region.unwrap_or_default(),
+    })
+}
+
+/// Counts a request blocked either globally or by the project settings.
+pub(super) fn record_blocked(scope: &'static str, location: Option<&Location>) {
+    let country = location.map_or_else(|| "unknown".to_owned(), |l| l.country.to_string());
+    counter!("geoblock_blocked_requests", 1, "scope" => scope, "country" => country);
+}
+
+/// Adds the [`Location`] of the request to its extensions and blocks it if
+/// it's from a blocked region.
+pub(super) async fn middleware<S, G>(
+    State(server): State<Arc<Server<S, G>>>,
+    mut req: Request,
+    next: Next,
+) -> Response
+where
+    G: geoip::Resolver,
+{
+    let location = locate(server.geoip_resolver.as_ref(), req.extensions().get());
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(MatchedPath::as_str);
+
+    if server.geoblock.is_blocked(route, location.as_ref()) {
+        record_blocked("global", location.as_ref());
+        return StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS.into_response();
+    }
+
+    if let Some(location) = location {
+        req.extensions_mut().insert(location);
+    }
+
+    next.run(req).await
+}
+
+#[test]
+fn test_geoblock() {
+    let location = |country: &str, subdivisions: &[&str]| Location {
+        country: country.into(),
+        subdivisions: subdivisions.iter().map(|s| s.to_string()).collect(),
+    };
+
+    let config = Config {
+        blocked_regions: vec!["CU".into(), "UA-43".into()],
+        ..Default::default()
+    };
+
+    let crimea = location("UA", &["43"]);
+    assert!(config.is_blocked(Some("/:project_id"), Some(&location("cu", &[]))));
+    assert!(config.is_blocked(Some("/:project_id"), Some(&crimea)));
+    assert!(!config.is_blocked(Some("/:project_id"), Some(&location("UA", &["30"]))));
+    assert!(!config.is_blocked(Some("/health"), Some(&crimea)));
+    assert!(!config.is_blocked(None, None));
+
+    let config = Config {
+        unavailable: Unavailable::FailClosed,
+        ..config
+    };
+    assert!(config.is_blocked(None, None));
+    assert!(!config.is_blocked(Some("/health"), None));
+}
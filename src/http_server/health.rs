@@ -1,7 +1,32 @@
-use {hyper::StatusCode, std::future};
+use {axum::extract::Json, hyper::StatusCode, serde::Serialize, std::future};
 
 pub fn get(
     provider: impl Fn() -> String + Clone + Send + 'static,
 ) -> impl Fn() -> future::Ready<(StatusCode, String)> + Clone + Send + 'static {
     move || future::ready((StatusCode::OK, provider()))
 }
+
+/// Readiness of the server to serve requests accurately, see `GET /ready`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Readiness {
+    /// Whether the GeoIP database is loaded, or GeoIP isn't configured.
+    pub geoip_ready: bool,
+
+    /// Unix timestamp of when the loaded GeoIP database was built.
+    pub geoip_build_epoch: Option<u64>,
+}
+
+pub fn ready(
+    provider: impl Fn() -> Readiness + Clone + Send + 'static,
+) -> impl Fn() -> future::Ready<(StatusCode, Json<Readiness>)> + Clone + Send + 'static {
+    move || {
+        let readiness = provider();
+        let status = if readiness.geoip_ready {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+        future::ready((status, Json(readiness)))
+    }
+}
@@ -1,15 +1,20 @@
 use {
-    super::CsrfToken,
     axum::{
         extract::Query,
-        response::{Html, IntoResponse},
+        response::{IntoResponse, Response},
     },
-    hyper::StatusCode,
+    base64::{engine::general_purpose::STANDARD as BASE64, Engine as _},
+    hyper::{header, http::HeaderValue},
     serde::Deserialize,
+    sha2::{Digest as _, Sha256},
+    std::sync::OnceLock,
 };
 
-const TEMPLATE: &str = r#"
-const csrfToken = '{token}';
+/// Legacy protocol: the parent sends a raw attestation id string and never
+/// hears back.
+const SCRIPT_V1: &str = r#"
+// the token is provided by the enclave document
+let csrfToken = document.querySelector('meta[name="csrf-token"]').content;
 // event subscribed by Verify Enclave
 window.addEventListener("message", (event) => {
     const attestationId = event.data
@@ -19,16 +24,26 @@ window.addEventListener("message", (event) => {
     fetch(`${window.location.protocol}//${window.location.host}/attestation`, {
         method: "POST",
         body: JSON.stringify({ attestationId, origin }),
-        headers: new Headers({ 
+        headers: new Headers({
             'content-type': 'application/json',
             'x-csrf-token': csrfToken
         })
     })
 })
-// token is valid for 1 hour, refresh every 55 minutes by reloading
+// token is valid for 1 hour, refresh it every 55 minutes and fall back to
+// reloading if that fails
 window.addEventListener("load", async () => {
-    setInterval(() => {
-        window.location.reload()
+    setInterval(async () => {
+        try {
+            const resp = await fetch(`${window.location.protocol}//${window.location.host}/token/refresh`, {
+                method: "POST",
+                headers: new Headers({ 'x-csrf-token': csrfToken })
+            })
+            if (!resp.ok) throw new Error(`http_${resp.status}`)
+            csrfToken = (await resp.json()).token
+        } catch (e) {
+            window.location.reload()
+        }
     }, 1000 * 60 * 55)
 })
 
@@ -36,15 +51,189 @@ window.addEventListener("load", async () => {
 window.parent.postMessage("verify_ready", "*")
 "#;
 
+/// JSON protocol: the parent sends
+/// `{ type: "verify_attestation", version: 2, attestationId, nonce }` and
+/// receives `{ type: "verify_attestation_result", version: 2, attestationId,
+/// nonce, success, error? }` back.
+const SCRIPT_V2: &str = r#"
+const PROTOCOL_VERSION = 2
+// the token is provided by the enclave document
+let csrfToken = document.querySelector('meta[name="csrf-token"]').content;
+// event subscribed by Verify Enclave
+window.addEventListener("message", async (event) => {
+    const { type, version, attestationId, nonce } = event.data || {}
+    if (type !== "verify_attestation" || version !== PROTOCOL_VERSION) return
+
+    const respond = (result) => event.source.postMessage({
+        type: "verify_attestation_result",
+        version: PROTOCOL_VERSION,
+        attestationId,
+        nonce,
+        ...result
+    }, event.origin)
+
+    if (typeof attestationId !== "string" || attestationId.length !== 64) {
+        return respond({ success: false, error: "invalid_attestation_id" })
+    }
+
+    try {
+        const resp = await fetch(`${window.location.protocol}//${window.location.host}/attestation`, {
+            method: "POST",
+            body: JSON.stringify({ attestationId, origin: event.origin }),
+            headers: new Headers({
+                'content-type': 'application/json',
+                'x-csrf-token': csrfToken
+            })
+        })
+        respond(resp.ok ? { success: true } : { success: false, error: `http_${resp.status}` })
+    } catch (e) {
+        respond({ success: false, error: "network_error" })
+    }
+})
+// token is valid for 1 hour, refresh it every 55 minutes and fall back to
+// reloading if that fails
+window.addEventListener("load", async () => {
+    setInterval(async () => {
+        try {
+            const resp = await fetch(`${window.location.protocol}//${window.location.host}/token/refresh`, {
+                method: "POST",
+                headers: new Headers({ 'x-csrf-token': csrfToken })
+            })
+            if (!resp.ok) throw new Error(`http_${resp.status}`)
+            csrfToken = (await resp.json()).token
+        } catch (e) {
+            window.location.reload()
+        }
+    }, 1000 * 60 * 55)
+})
+
+// notify the SDK that the iframe is ready
+window.parent.postMessage({ type: "verify_ready", version: PROTOCOL_VERSION }, "*")
+"#;
+
+/// Version of the `postMessage` protocol spoken by the enclave script.
+///
+/// Selected by the SDK via the `protocol` query parameter of the enclave URL,
+/// so older SDKs keep getting the protocol they understand.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(try_from = "u8")]
+pub(super) enum Protocol {
+    #[default]
+    V1,
+    V2,
+}
+
+impl TryFrom<u8> for Protocol {
+    type Error = String;
+
+    fn try_from(version: u8) -> Result<Self, Self::Error> {
+        match version {
+            1 => Ok(Self::V1),
+            2 => Ok(Self::V2),
+            v => Err(format!("Unsupported protocol version: {v}")),
+        }
+    }
+}
+
+impl Protocol {
+    pub(super) fn as_u8(self) -> u8 {
+        match self {
+            Self::V1 => 1,
+            Self::V2 => 2,
+        }
+    }
+
+    fn script(self) -> &'static str {
+        match self {
+            Self::V1 => SCRIPT_V1,
+            Self::V2 => SCRIPT_V2,
+        }
+    }
+
+    pub(super) fn asset(self) -> &'static Asset {
+        static V1: OnceLock<Asset> = OnceLock::new();
+        static V2: OnceLock<Asset> = OnceLock::new();
+
+        let asset = match self {
+            Self::V1 => &V1,
+            Self::V2 => &V2,
+        };
+
+        asset.get_or_init(|| Asset::new(self.script()))
+    }
+}
+
+/// Content hash of a script.
+pub(super) struct Asset {
+    /// Hex-encoded prefix of the hash, used to version the URL of the script.
+    pub version: String,
+
+    /// Subresource Integrity metadata of the script.
+    pub integrity: String,
+}
+
+impl Asset {
+    fn new(script: &str) -> Self {
+        let digest = Sha256::digest(script);
+
+        Self {
+            version: digest[..8].iter().map(|b| format!("{b:02x}")).collect(),
+            integrity: format!("sha256-{}", BASE64.encode(digest)),
+        }
+    }
+}
+
 #[derive(Deserialize)]
 pub(super) struct Params {
-    token: String,
+    #[serde(default)]
+    protocol: Protocol,
+    v: Option<String>,
 }
 
-pub(super) async fn get(query: Query<Params>) -> Result<impl IntoResponse, StatusCode> {
-    if !CsrfToken::validate_format(&query.token) {
-        return Err(StatusCode::BAD_REQUEST);
+pub(super) async fn get(query: Query<Params>) -> Response {
+    let mut resp = (
+        [(header::CONTENT_TYPE, "text/javascript; charset=utf-8")],
+        query.protocol.script(),
+    )
+        .into_response();
+
+    // Only the current version may be cached forever. Requests of the other
+    // versions (or without one) fall back to the default `Cache-Control`.
+    if query.v.as_deref() == Some(query.protocol.asset().version.as_str()) {
+        resp.headers_mut().insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("public, max-age=31536000, immutable"),
+        );
     }
 
-    Ok(Html(TEMPLATE.replacen("{token}", &query.token, 1)))
+    resp
+}
+
+#[test]
+fn test_asset() {
+    for protocol in [Protocol::V1, Protocol::V2] {
+        let asset = protocol.asset();
+        let digest = asset
+            .integrity
+            .strip_prefix("sha256-")
+            .and_then(|b64| BASE64.decode(b64).ok())
+            .unwrap();
+
+        assert_eq!(digest, Sha256::digest(protocol.script()).to_vec());
+        assert_eq!(asset.version.len(), 16);
+        assert!(asset.version.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    assert_ne!(Protocol::V1.asset().version, Protocol::V2.asset().version);
+}
+
+#[test]
+fn test_protocol_version() {
+    assert_eq!(Protocol::try_from(1), Ok(Protocol::V1));
+    assert_eq!(Protocol::try_from(2), Ok(Protocol::V2));
+    assert!(Protocol::try_from(3).is_err());
+
+    for protocol in [Protocol::V1, Protocol::V2] {
+        assert_eq!(Protocol::try_from(protocol.as_u8()), Ok(protocol));
+    }
 }
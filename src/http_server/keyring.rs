@@ -0,0 +1,212 @@
+use {
+    crate::util::watch,
+    anyhow::Context as _,
+    jsonwebtoken::{DecodingKey, EncodingKey},
+    metrics::counter,
+    serde::Deserialize,
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+        time::Duration,
+    },
+    tap::TapFallible as _,
+    tracing::{info, warn},
+};
+
+/// Id of the key derived from the legacy single `SECRET`.
+const LEGACY_KEY_ID: &str = "default";
+
+/// Configuration of the [`Keyring`] used for signing and verifying tokens.
+///
+/// The keys are taken from the first source being specified:
+/// - the JSON [`KeyringConfig::file`], which gets reloaded on change;
+/// - the [`KeyringConfig::keys`] list;
+/// - the legacy single secret.
+#[derive(Clone, Debug, Deserialize)]
+pub struct KeyringConfig {
+    /// Path to a JSON file having the same shape as [`KeyringFile`].
+    pub file: Option<PathBuf>,
+
+    /// How often to check the [`KeyringConfig::file`] for changes.
+    #[serde(default = "default_reload_interval_secs")]
+    pub reload_interval_secs: u64,
+
+    /// Id of the key to sign new tokens with.
+    pub signing_key_id: Option<String>,
+
+    /// List of `<key_id>:<secret>` pairs.
+    #[serde(default)]
+    pub keys: Vec<String>,
+}
+
+impl Default for KeyringConfig {
+    fn default() -> Self {
+        Self {
+            file: None,
+            reload_interval_secs: default_reload_interval_secs(),
+            signing_key_id: None,
+            keys: Vec::new(),
+        }
+    }
+}
+
+fn default_reload_interval_secs() -> u64 {
+    60
+}
+
+impl KeyringConfig {
+    /// Builds the initial [`Keyring`], falling back to the `legacy_secret` if
+    /// no keys are configured.
+    pub fn keyring(&self, legacy_secret: Option<&str>) -> Result<Keyring, anyhow::Error> {
+        if let Some(path) = &self.file {
+            return Keyring::from_file(path);
+        }
+
+        if !self.keys.is_empty() {
+            let keys = self
+                .keys
+                .iter()
+                .map(|pair| {
+                    pair.split_once(':')
+                        .map(|(id, secret)| (id.to_owned(), secret.to_owned()))
+                        .context("Key should be specified as `<key_id>:<secret>`")
+                })
+                .collect::<Result<_, _>>()?;
+
+            let signing_key_id = self
+                .signing_key_id
+                .clone()
+                .context("signing_key_id is not specified")?;
+
+            return KeyringFile {
+                signing_key_id,
+                keys,
+            }
+            .try_into();
+        }
+
+        legacy_secret
+            .map(Keyring::from_secret)
+            .context("Neither keyring nor secret is specified")
+    }
+}
+
+/// Contents of a keyring file.
+#[derive(Debug, Deserialize)]
+pub struct KeyringFile {
+    pub signing_key_id: String,
+
+    /// Secrets by their key ids.
+    pub keys: HashMap<String, String>,
+}
+
+/// Set of keys tokens are verified with, one of which is used for signing.
+///
+/// Tokens carry the id of the key they were signed with in the `kid` header,
+/// so keys can be rotated without invalidating tokens issued before:
+/// 1. add a new key, keeping the old one as the signing key;
+/// 2. once every instance has it, make the new key the signing one;
+/// 3. once the tokens signed with the old key expire, remove it.
+pub struct Keyring {
+    signing_key_id: String,
+    encoding_key: EncodingKey,
+    decoding_keys: HashMap<String, DecodingKey>,
+}
+
+impl Keyring {
+    /// Builds a keyring consisting of a single key.
+    pub fn from_secret(secret: &str) -> Self {
+        Self {
+            signing_key_id: LEGACY_KEY_ID.to_owned(),
+            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
+            decoding_keys: HashMap::from([(
+                LEGACY_KEY_ID.to_owned(),
+                DecodingKey::from_secret(secret.as_bytes()),
+            )]),
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, anyhow::Error> {
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("Failed to read keyring file {}", path.display()))?;
+
+        serde_json::from_slice::<KeyringFile>(&bytes)
+            .context("Failed to parse keyring file")?
+            .try_into()
+    }
+
+    pub(super) fn signing_key(&self) -> (&str, &EncodingKey) {
+        (&self.signing_key_id, &self.encoding_key)
+    }
+
+    /// Returns the keys a token having the provided `kid` header should be
+    /// verified with.
+    ///
+    /// Tokens issued before the keys got ids are tried against every key.
+    pub(super) fn decoding_keys<'a>(
+        &'a self,
+        kid: Option<&'a str>,
+    ) -> impl Iterator<Item = &'a DecodingKey> {
+        self.decoding_keys
+            .iter()
+            .filter(move |(id, _)| kid.is_none() || kid == Some(id.as_str()))
+            .map(|(_, key)| key)
+    }
+}
+
+impl TryFrom<KeyringFile> for Keyring {
+    type Error = anyhow::Error;
+
+    fn try_from(file: KeyringFile) -> Result<Self, Self::Error> {
+        let secret = file
+            .keys
+            .get(&file.signing_key_id)
+            .context("Signing key is missing from the keyring")?;
+
+        Ok(Self {
+            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
+            decoding_keys: file
+                .keys
+                .iter()
+                .map(|(id, secret)| (id.clone(), DecodingKey::from_secret(secret.as_bytes())))
+                .collect(),
+            signing_key_id: file.signing_key_id,
+        })
+    }
+}
+
+/// Periodically reloads the [`Keyring`] from the file at `path` whenever its
+/// modification time changes, keeping the current one if the file is invalid.
+pub(super) async fn watch_file(path: PathBuf, interval: Duration, on_reload: impl Fn(Keyring)) {
+    watch::watch_file(path, interval, |path| {
+        let keyring = Keyring::from_file(path).tap_err(|e| {
+            warn!("Failed to reload keyring: {e:?}");
+            counter!("keyring_reload_errors", 1);
+        })?;
+
+        info!(signing_key_id = %keyring.signing_key_id, "Keyring reloaded");
+        on_reload(keyring);
+        Ok(())
+    })
+    .await
+}
+
+#[test]
+fn test_keyring() {
+    let keyring = Keyring::try_from(KeyringFile {
+        signing_key_id: "b".into(),
+        keys: HashMap::from([("a".into(), "1".into()), ("b".into(), "2".into())]),
+    })
+    .unwrap();
+
+    assert_eq!(keyring.signing_key().0, "b");
+    assert_eq!(keyring.decoding_keys(Some("a")).count(), 1);
+    assert_eq!(keyring.decoding_keys(Some("c")).count(), 0);
+    assert_eq!(keyring.decoding_keys(None).count(), 2);
+
+    assert!(Keyring::try_from(KeyringFile {
+        signing_key_id: "c".into(),
+        keys: HashMap::from([("a".into(), "1".into())]),
+    })
+    .is_err());
+}
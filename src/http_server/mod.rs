@@ -1,5 +1,6 @@
 use {
     crate::{
+        util::time::unix_timestamp,
         ContextualCommand,
         Domain,
         GetAttestation,
@@ -8,20 +9,22 @@ use {
         GetVerifyStatusError,
         GetVerifyStatusResult,
         Handle,
+        Location,
         ProjectId,
         SetAttestation,
         SetAttestationResult,
+        VerifyConfig,
         VerifyStatus,
     },
+    ::metrics::counter,
     async_trait::async_trait,
     axum::{
-        extract::{FromRequestParts, Path},
+        extract::{FromRequestParts, Json, Path, Query},
         http::request,
         response::{Html, IntoResponse, Response},
         routing::{get, post},
         Router,
     },
-    axum_client_ip::InsecureClientIp,
     axum_extra::{headers::UserAgent, TypedHeader},
     axum_prometheus::{EndpointLabel, PrometheusMetricLayerBuilder as MetricLayerBuilder},
     futures::FutureExt,
@@ -32,40 +35,67 @@ use {
         Method,
         StatusCode,
     },
+    reqwest::Url,
     serde::{Deserialize, Serialize},
     std::{
         convert::Infallible,
         future::{Future, IntoFuture},
         iter,
         net::SocketAddr,
-        sync::Arc,
+        path::PathBuf,
+        sync::{Arc, RwLock},
+        time::Duration,
     },
-    tap::{Pipe, Tap},
+    tap::{Pipe, Tap, TapFallible as _},
     tokio::net::TcpListener,
     tower_http::cors::{self, CorsLayer},
-    tracing::{info, instrument},
-    wc::geoip::{
-        self,
-        block::{middleware::GeoBlockLayer, BlockingPolicy as GeoBlockingPolicy},
-    },
+    tracing::{info, instrument, warn},
+    wc::geoip,
 };
 
 mod attestation;
+pub mod client_ip;
+mod diagnostics;
+pub mod geoblock;
 mod health;
 mod index_js;
+mod keyring;
 mod metrics;
+mod project_sync;
+pub mod rate_limit;
+mod security_headers;
+mod token;
+
+pub use {
+    client_ip::ClientIp,
+    health::Readiness,
+    keyring::{Keyring, KeyringConfig},
+    project_sync::ProjectSyncWebhook,
+    rate_limit::RateLimiter,
+    security_headers::SecurityHeaders,
+};
 
-pub struct ServerConfig<'a> {
+pub struct ServerConfig {
     pub port: u16,
     pub metrics_port: u16,
-    pub secret: &'a [u8],
-    pub blocked_countries: Vec<String>,
+    pub token_manager: TokenManager,
+    pub geoblock: geoblock::Config,
+    pub security_headers: SecurityHeaders,
+    pub client_ip: client_ip::Config,
+
+    /// Enables `POST /v1/projects/sync`.
+    pub project_sync: Option<ProjectSyncWebhook>,
+
+    /// Requests aren't rate limited if not set.
+    pub rate_limiter: Option<RateLimiter>,
 }
 
 struct Server<S, G> {
     service: S,
     geoip_resolver: Option<G>,
+    geoblock: geoblock::Config,
     token_manager: TokenManager,
+    project_sync: Option<ProjectSyncWebhook>,
 }
 
 type Command<T> = ContextualCommand<T, RequestInfo>;
@@ -84,28 +114,53 @@ impl<S, G> Server<S, G> {
     }
 }
 
+/// Signs and verifies tokens using the current [`Keyring`], which may be
+/// swapped at runtime.
 #[derive(Clone)]
 pub struct TokenManager {
-    encoding_key: jsonwebtoken::EncodingKey,
-    decoding_key: jsonwebtoken::DecodingKey,
+    keyring: Arc<RwLock<Arc<Keyring>>>,
 }
 
 impl TokenManager {
-    pub fn new(secret: &[u8]) -> Self {
+    pub fn new(keyring: Keyring) -> Self {
         Self {
-            encoding_key: jsonwebtoken::EncodingKey::from_secret(secret),
-            decoding_key: jsonwebtoken::DecodingKey::from_secret(secret),
+            keyring: Arc::new(RwLock::new(Arc::new(keyring))),
         }
     }
+
+    fn keyring(&self) -> Arc<Keyring> {
+        self.keyring.read().unwrap().clone()
+    }
+
+    pub fn set_keyring(&self, keyring: Keyring) {
+        *self.keyring.write().unwrap() = Arc::new(keyring);
+    }
+
+    /// Reloads the [`Keyring`] from the file at `path` whenever it changes.
+    pub async fn watch_keyring_file(self, path: PathBuf, interval: Duration) {
+        keyring::watch_file(path, interval, |keyring| self.set_keyring(keyring)).await
+    }
+
+    fn encode(&self, claims: &impl Serialize) -> jsonwebtoken::errors::Result<String> {
+        let keyring = self.keyring();
+        let (kid, key) = keyring.signing_key();
+        let header = jsonwebtoken::Header {
+            kid: Some(kid.to_owned()),
+            ..Default::default()
+        };
+
+        jsonwebtoken::encode(&header, claims, key)
+    }
 }
 
 type State<S, G> = axum::extract::State<Arc<Server<S, G>>>;
 
 pub async fn run<S, G>(
-    cfg: ServerConfig<'_>,
+    cfg: ServerConfig,
     service: S,
     metrics_provider: impl Fn() -> String + Clone + Send + 'static,
     health_provider: impl Fn() -> String + Clone + Send + 'static,
+    readiness_provider: impl Fn() -> Readiness + Clone + Send + 'static,
     geoip_resolver: Option<G>,
     shutdown: impl Future + Send + 'static,
 ) -> Result<(), anyhow::Error>
@@ -129,34 +184,51 @@ where
         .with_endpoint_label_type(EndpointLabel::MatchedPathWithFallbackFn(|_| String::new()))
         .build();
 
-    let state = Server {
+    let state = Arc::new(Server {
         service,
-        geoip_resolver: geoip_resolver.clone(),
-        token_manager: TokenManager::new(cfg.secret),
-    };
+        geoip_resolver,
+        geoblock: cfg.geoblock,
+        token_manager: cfg.token_manager,
+        project_sync: cfg.project_sync,
+    });
 
-    let server: Router = Router::new()
+    let server = Router::new()
         .route("/attestation/:attestation_id", get(attestation::get))
+        .route("/v1/projects/:project_id/verify", get(diagnostics::get))
         .layer(cors_layer)
         .route("/health", get(health::get(health_provider)))
+        .route("/ready", get(health::ready(readiness_provider)))
         .route("/attestation", post(attestation::post))
         .route("/index.js", get(index_js::get))
-        .route("/:project_id", get(root))
+        .route("/token/refresh", post(token::refresh))
+        .route("/v1/projects/sync", post(project_sync::post))
+        .route("/:project_id", get(root));
+    let server = match cfg.rate_limiter {
+        Some(limiter) => server.layer(axum::middleware::from_fn_with_state(
+            Arc::new(limiter),
+            rate_limit::middleware,
+        )),
+        None => server,
+    };
+    let server: Router = server
+        .layer(axum::middleware::from_fn_with_state(
+            Arc::new(cfg.security_headers),
+            security_headers::middleware,
+        ))
         .layer(metrics_layer)
-        .with_state(Arc::new(state));
-    let server = if let (Some(resolver), false) = (geoip_resolver, cfg.blocked_countries.is_empty())
-    {
-        server.layer(GeoBlockLayer::new(
-            resolver,
-            cfg.blocked_countries,
-            GeoBlockingPolicy::AllowAll,
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            geoblock::middleware,
         ))
-    } else {
-        server
-    };
+        .with_state(state);
+    // Outermost, so the client IP is known to every other layer.
+    let server = server.layer(axum::middleware::from_fn_with_state(
+        Arc::new(cfg.client_ip),
+        client_ip::middleware,
+    ));
     let listener = TcpListener::bind(&SocketAddr::from(([0, 0, 0, 0], cfg.port))).await?;
     let server = server
-        .into_make_service()
+        .into_make_service_with_connect_info::<SocketAddr>()
         .pipe(|svc| axum::serve(listener, svc))
         .pipe(|s| s.with_graceful_shutdown(shutdown.clone()))
         .tap(|_| info!("Serving at :{}", cfg.port))
@@ -180,13 +252,23 @@ where
     Ok(())
 }
 
-fn index_html(token: &str) -> String {
+fn index_html(token: &str, nonce: &str, protocol: index_js::Protocol) -> String {
+    let index_js::Asset { version, integrity } = protocol.asset();
+    let protocol = protocol.as_u8();
+
     format!(
-        "<!-- index.html --><html><head><script \
-         src=\"/index.js?token={token}\"></script></head></html>"
+        "<!-- index.html --><html><head><meta name=\"csrf-token\" content=\"{token}\"><script \
+         nonce=\"{nonce}\" src=\"/index.js?protocol={protocol}&amp;v={version}\" \
+         integrity=\"{integrity}\"></script></head></html>"
     )
 }
 
+#[derive(Debug, Deserialize)]
+struct EnclaveParams {
+    #[serde(default)]
+    protocol: index_js::Protocol,
+}
+
 const UNKNOWN_PROJECT_MSG: &str = "Project with the provided ID doesn't exist. Please, ensure \
                                    that the project is registered on cloud.walletconnect.com";
 
@@ -194,21 +276,53 @@ const UNKNOWN_PROJECT_MSG: &str = "Project with the provided ID doesn't exist. P
 async fn root<S, G>(
     s: State<S, G>,
     Path(project_id): Path<ProjectId>,
+    Query(params): Query<EnclaveParams>,
+    req_headers: HeaderMap,
     request_info: RequestInfo,
 ) -> Result<Response, Response>
 where
     S: for<'a> Handle<Command<GetVerifyStatus<'a>>, Result = GetVerifyStatusResult>,
 {
+    let location = request_info.location.clone();
     let cmd = GetVerifyStatus {
         project_id: &project_id,
+        location: location.as_ref(),
     };
 
-    Ok(match s.handle(cmd, request_info).await? {
-        VerifyStatus::Disabled => String::new().into_response(),
-        VerifyStatus::Enabled { verified_domains } => {
-            let token = s.token_manager.generate_csrf_token()?;
-            let html = index_html(&token);
-            let csp = build_content_security_header(verified_domains);
+    Ok(match s.handle(cmd, request_info).await?.status {
+        status @ (VerifyStatus::Disabled
+        | VerifyStatus::NoVerifiedDomains
+        | VerifyStatus::DisabledInCountry) => {
+            if matches!(status, VerifyStatus::DisabledInCountry) {
+                geoblock::record_blocked("project", location.as_ref());
+            }
+
+            let headers = [(verify_status_header_name(), status.as_str())];
+
+            // The body stays empty for the enclave, the status is only explained to
+            // those asking for JSON.
+            if accepts_json(&req_headers) {
+                let body = VerifyStatusBody {
+                    status: status.as_str(),
+                    message: status.description(),
+                };
+                (headers, Json(body)).into_response()
+            } else {
+                (headers, String::new()).into_response()
+            }
+        }
+        VerifyStatus::Enabled {
+            verified_domains,
+            config,
+        } => {
+            let csp_domains = build_content_security_header(&verified_domains, &config);
+            let token = s
+                .token_manager
+                .generate_csrf_token(project_id)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+            let nonce = security_headers::nonce();
+            let html = index_html(&token, &nonce, params.protocol);
+            let csp = security_headers::enclave_content_security_policy(&nonce, &csp_domains);
             let headers = [
                 (header::CONTENT_SECURITY_POLICY, csp),
                 (CsrfToken::header_name(), token),
@@ -218,6 +332,58 @@ where
     })
 }
 
+/// Looks up the verified domains and configuration of the project a
+/// [`CsrfToken`] was issued for, rejecting the request unless Verify is
+/// enabled for it.
+async fn enabled_project<S, G>(
+    s: &Server<S, G>,
+    token: &CsrfToken,
+    request_info: RequestInfo,
+    kind: RequestKind,
+) -> Result<(Vec<Domain>, VerifyConfig), Response>
+where
+    S: for<'a> Handle<Command<GetVerifyStatus<'a>>, Result = GetVerifyStatusResult>,
+{
+    let location = request_info.location.clone();
+    let cmd = GetVerifyStatus {
+        project_id: &token.project_id,
+        location: location.as_ref(),
+    };
+    let request_info = RequestInfo {
+        kind,
+        ..request_info
+    };
+
+    match s.handle(cmd, request_info).await?.status {
+        VerifyStatus::Enabled {
+            verified_domains,
+            config,
+        } => Ok((verified_domains, config)),
+        status => {
+            let headers = [(verify_status_header_name(), status.as_str())];
+            Err((StatusCode::FORBIDDEN, headers, status.description()).into_response())
+        }
+    }
+}
+
+/// Name of the header telling the [`VerifyStatus`] of a project.
+const fn verify_status_header_name() -> HeaderName {
+    HeaderName::from_static("x-verify-status")
+}
+
+#[derive(Debug, Serialize)]
+struct VerifyStatusBody {
+    status: &'static str,
+    message: &'static str,
+}
+
+fn accepts_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("application/json"))
+}
+
 impl From<GetVerifyStatusError> for Response {
     fn from(e: GetVerifyStatusError) -> Self {
         match e {
@@ -229,16 +395,30 @@ impl From<GetVerifyStatusError> for Response {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct RequestInfo {
     pub user_agent: Option<String>,
-    pub country: Option<Arc<str>>,
+
+    /// Added by the [`geoblock::middleware`].
+    pub location: Option<Location>,
+
+    pub kind: RequestKind,
+}
+
+/// Why a command is handled, telling apart the [`GetVerifyStatus`] commands of
+/// the enclave loads from the rest.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum RequestKind {
+    #[default]
+    Enclave,
+    TokenRefresh,
+    Diagnostics,
+    Attestation,
 }
 
 #[async_trait]
 impl<S, G> FromRequestParts<Arc<Server<S, G>>> for RequestInfo
 where
-    G: geoip::Resolver,
     Server<S, G>: Sync + Send,
 {
     type Rejection = Infallible;
@@ -252,19 +432,30 @@ where
                 .await
                 .ok()
                 .map(|ua| ua.as_str().to_string()),
-            country: server.geoip_resolver.as_ref().and_then(|resolver| {
-                InsecureClientIp::from(&parts.headers, &parts.extensions)
-                    .ok()
-                    .and_then(|ip| resolver.lookup_geo_data(ip.0).ok())
-                    .and_then(|data| data.country)
-            }),
+            location: parts.extensions.get::<Location>().cloned(),
+            kind: RequestKind::default(),
         })
     }
 }
 
-#[derive(Serialize, Deserialize)]
+/// Claims of a CSRF token issued to the enclave of a project.
+#[derive(Debug, Serialize, Deserialize)]
 pub struct CsrfToken {
     exp: usize,
+    iat: usize,
+
+    /// Random value making every issued token unique.
+    nonce: String,
+
+    /// Project the token was issued for. Its verified domains and Verify
+    /// configuration are looked up whenever the token is used, keeping the
+    /// token small.
+    project_id: ProjectId,
+
+    /// When the first token of the refresh chain was issued, the same as
+    /// `iat` if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    oiat: Option<usize>,
 }
 
 impl CsrfToken {
@@ -274,74 +465,202 @@ impl CsrfToken {
         HeaderName::from_static("x-csrf-token")
     }
 
-    /// Validates the format of the token without checking either signature or
-    /// claims.
-    fn validate_format(s: &str) -> bool {
-        s.chars()
-            .all(|c| c.is_ascii_alphanumeric() | matches!(c, '.' | '-' | '_'))
+    /// Checks that the `origin` is allowed for the project the token was
+    /// issued for, given its current verified domains and configuration.
+    fn check_origin(
+        &self,
+        origin: &str,
+        domains: &[Domain],
+        config: &VerifyConfig,
+    ) -> Result<(), CsrfTokenError> {
+        if !is_origin_allowed(origin, domains, config) {
+            warn!(project_id = %self.project_id.as_ref(), origin, "CSRF token origin mismatch");
+            counter!("csrf_token_origin_mismatches", 1);
+            return Err(CsrfTokenError::OriginMismatch);
+        }
+
+        Ok(())
+    }
+}
+
+/// Error of validating a [`CsrfToken`].
+#[derive(Debug, thiserror::Error)]
+pub enum CsrfTokenError {
+    #[error("Missing or malformed CSRF token")]
+    Invalid,
+
+    #[error("Origin is not allowed for the project the CSRF token was issued for")]
+    OriginMismatch,
+
+    #[error("CSRF token can't be refreshed anymore, the enclave has to be reloaded")]
+    MaxAgeExceeded,
+
+    #[error(transparent)]
+    Encoding(#[from] jsonwebtoken::errors::Error),
+}
+
+impl IntoResponse for CsrfTokenError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Encoding(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+            _ => (StatusCode::FORBIDDEN, self.to_string()).into_response(),
+        }
     }
 }
 
+const TOKEN_TTL_SECS: usize = 60 * 60; // 1 hour
+
+/// For how long a token can be refreshed after the first token of its chain
+/// was issued.
+const TOKEN_MAX_AGE_SECS: usize = 24 * 60 * 60; // 1 day
+
 impl TokenManager {
-    pub fn generate_csrf_token(&self) -> Result<String, Response> {
-        use jsonwebtoken::{encode, get_current_timestamp, Header};
+    pub fn generate_csrf_token(
+        &self,
+        project_id: ProjectId,
+    ) -> jsonwebtoken::errors::Result<String> {
+        self.issue_csrf_token(project_id, None)
+    }
 
-        const TTL_SECS: usize = 60 * 60; // 1 hour
+    /// Issues a token continuing the refresh chain of the `token`, failing if
+    /// the chain is older than [`TOKEN_MAX_AGE_SECS`].
+    fn refresh_csrf_token(&self, token: &CsrfToken) -> Result<String, CsrfTokenError> {
+        let oiat = token.oiat.unwrap_or(token.iat);
+        let now = unix_timestamp() as usize;
+        if now.saturating_sub(oiat) >= TOKEN_MAX_AGE_SECS {
+            counter!("csrf_token_max_age_exceeded", 1);
+            return Err(CsrfTokenError::MaxAgeExceeded);
+        }
+
+        Ok(self.issue_csrf_token(token.project_id, Some(oiat))?)
+    }
 
+    fn issue_csrf_token(
+        &self,
+        project_id: ProjectId,
+        oiat: Option<usize>,
+    ) -> jsonwebtoken::errors::Result<String> {
+        let now = unix_timestamp() as usize;
         let claims = CsrfToken {
-            exp: get_current_timestamp() as usize + TTL_SECS,
+            exp: now + TOKEN_TTL_SECS,
+            iat: now,
+            nonce: security_headers::nonce(),
+            project_id,
+            oiat,
         };
 
-        encode(&Header::default(), &claims, &self.encoding_key)
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())
+        self.encode(&claims)
     }
 
-    fn validate_csrf_token(&self, headers: &HeaderMap<HeaderValue>) -> Result<(), StatusCode> {
-        use jsonwebtoken::{decode, Validation};
+    /// Decodes the [`CsrfToken`] from the headers, checking its signature and
+    /// expiration.
+    fn decode_csrf_token(
+        &self,
+        headers: &HeaderMap<HeaderValue>,
+    ) -> Result<CsrfToken, CsrfTokenError> {
+        use jsonwebtoken::{decode, decode_header, Validation};
 
-        let try_validate = |headers: &HeaderMap<HeaderValue>| {
+        let try_decode = |headers: &HeaderMap<HeaderValue>| {
             let token = headers.get(CsrfToken::header_name())?.to_str().ok()?;
+            let kid = decode_header(token).ok()?.kid;
+            let keyring = self.keyring();
+            let data = keyring
+                .decoding_keys(kid.as_deref())
+                .find_map(|key| decode::<CsrfToken>(token, key, &Validation::default()).ok())?;
 
-            decode::<CsrfToken>(token, &self.decoding_key, &Validation::default())
-                .map(drop)
-                .ok()
+            Some(data.claims)
         };
 
-        try_validate(headers).ok_or(StatusCode::FORBIDDEN)
+        try_decode(headers)
+            .ok_or(CsrfTokenError::Invalid)
+            .tap_err(|_| counter!("csrf_token_validation_errors", 1))
     }
 }
 
-fn build_content_security_header(domains: Vec<Domain>) -> String {
-    let urls = domains.iter().map(AsRef::as_ref).flat_map(|domain| {
-        let proto = if domain == "localhost" {
-            "http://"
+/// Checks whether the `origin` is allowed to use the enclave of a project
+/// having the provided verified domains and configuration.
+///
+/// Mirrors the `frame-ancestors` directive built by
+/// [`build_content_security_header`].
+fn is_origin_allowed(origin: &str, domains: &[Domain], config: &VerifyConfig) -> bool {
+    let Ok(url) = Url::parse(origin) else {
+        return false;
+    };
+    let (scheme, Some(host)) = (url.scheme(), url.host_str()) else {
+        return false;
+    };
+
+    let is_local = host == "localhost" || host.ends_with(".localhost") || host == "127.0.0.1";
+    if is_local && config.allow_localhost && matches!(scheme, "http" | "https") {
+        return true;
+    }
+
+    // Domains are specified without a port in the `frame-ancestors` directive.
+    if url.port().is_some() {
+        return false;
+    }
+
+    domains.iter().map(AsRef::as_ref).any(|domain: &String| {
+        let is_scheme_allowed = if domain == "localhost" {
+            scheme == "http"
         } else {
-            "https://"
+            config.allowed_schemes.iter().any(|s| s == scheme)
         };
 
-        // `*.domain` doesn't match `domain` by the Content-Security-Policy spec, so
-        // we are specifying both.
-        //
-        // See the test for this function if you have any doubts.
-        [" ", proto, "*.", domain, " ", proto, domain]
-    });
+        let is_subdomain = host
+            .strip_suffix(domain.as_str())
+            .is_some_and(|sub| sub.ends_with('.'));
+
+        is_scheme_allowed && (host == domain || is_subdomain)
+    })
+}
+
+fn build_content_security_header(domains: &[Domain], config: &VerifyConfig) -> String {
+    let http = ["http".to_owned()];
+
+    let urls = domains
+        .iter()
+        .map(AsRef::as_ref)
+        .flat_map(|domain: &String| {
+            let schemes = if domain == "localhost" {
+                &http[..]
+            } else {
+                &config.allowed_schemes[..]
+            };
+
+            // `*.domain` doesn't match `domain` by the Content-Security-Policy spec, so
+            // we are specifying both.
+            //
+            // See the test for this function if you have any doubts.
+            schemes
+                .iter()
+                .filter(|scheme| crate::is_valid_scheme(scheme))
+                .flat_map(move |scheme| [" ", scheme, "://*.", domain, " ", scheme, "://", domain])
+        });
 
     let localhost_urls = " https://localhost:* http://localhost:* https://*.localhost:* http://*.localhost:* https://127.0.0.1:* http://127.0.0.1:*";
 
     iter::once("frame-ancestors")
         .chain(urls)
-        .chain(iter::once(localhost_urls))
+        .chain(config.allow_localhost.then_some(localhost_urls))
         .collect()
 }
 
 #[test]
 fn test_build_content_security_header() {
-    fn case(domains: &[&str], expected: &str) {
-        let domains = domains.iter().map(|s| Domain::from(s.to_string()));
-        let got = build_content_security_header(domains.collect());
+    fn case_with_config(domains: &[&str], config: &VerifyConfig, expected: &str) {
+        let domains: Vec<_> = domains
+            .iter()
+            .map(|s| Domain::from(s.to_string()))
+            .collect();
+        let got = build_content_security_header(&domains, config);
         assert_eq!(&got, expected);
     }
 
+    fn case(domains: &[&str], expected: &str) {
+        case_with_config(domains, &VerifyConfig::default(), expected)
+    }
+
     case(
         &["walletconnect.com"],
         "frame-ancestors https://*.walletconnect.com https://walletconnect.com https://localhost:* http://localhost:* https://*.localhost:* http://*.localhost:* https://127.0.0.1:* http://127.0.0.1:*",
@@ -353,19 +672,168 @@ fn test_build_content_security_header() {
                          https://*.vercel.app https://vercel.app \
                          http://*.localhost http://localhost https://localhost:* http://localhost:* https://*.localhost:* http://*.localhost:* https://127.0.0.1:* http://127.0.0.1:*",
     );
+
+    case_with_config(
+        &["walletconnect.com"],
+        &VerifyConfig {
+            allowed_schemes: vec!["https".into(), "http".into()],
+            allow_localhost: false,
+            ..Default::default()
+        },
+        "frame-ancestors https://*.walletconnect.com https://walletconnect.com \
+                         http://*.walletconnect.com http://walletconnect.com",
+    );
+
+    // Invalid schemes can't inject directives.
+    case_with_config(
+        &["walletconnect.com"],
+        &VerifyConfig {
+            allowed_schemes: vec!["https: *; script-src *".into(), "ipfs".into()],
+            allow_localhost: false,
+            ..Default::default()
+        },
+        "frame-ancestors ipfs://*.walletconnect.com ipfs://walletconnect.com",
+    );
+}
+
+#[cfg(test)]
+fn test_csrf_token(tm: &TokenManager) -> HeaderMap {
+    let project_id = "0123456789abcdef0123456789abcdef".parse().unwrap();
+    let token = tm.generate_csrf_token(project_id).unwrap();
+
+    // Tokens are embedded into the enclave document as is.
+    assert!(token
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() | matches!(c, '.' | '-' | '_')));
+
+    HeaderMap::from_iter([(CsrfToken::header_name(), token.parse().unwrap())])
 }
 
 #[test]
 fn generated_csrf_tokens_are_valid() {
-    let tm = TokenManager::new(&[]);
-    let token = tm.generate_csrf_token().unwrap();
-    assert!(CsrfToken::validate_format(&token))
+    let tm = TokenManager::new(Keyring::from_secret(""));
+    let headers = test_csrf_token(&tm);
+
+    let token = tm.decode_csrf_token(&headers).unwrap();
+    assert_eq!(
+        token.project_id.as_ref(),
+        "0123456789abcdef0123456789abcdef"
+    );
+}
+
+#[test]
+fn csrf_validation_checks_jwt_signature() {
+    let headers = test_csrf_token(&TokenManager::new(Keyring::from_secret("other")));
+
+    assert!(matches!(
+        TokenManager::new(Keyring::from_secret("")).decode_csrf_token(&headers),
+        Err(CsrfTokenError::Invalid)
+    ));
 }
 
 #[test]
-fn csrf_validation_checks_jwt_header_and_payload() {
-    let valid_header_invalid_payload =
-        "eyJ0eXAiOiJKV1QiLCJhbGciOiJIUzI1NiJ9.<svg/onload=alert(document.domain)>";
+fn csrf_tokens_survive_key_rotation() {
+    let keyring = |signing_key_id: &str, keys: &[&str]| {
+        keyring::KeyringFile {
+            signing_key_id: signing_key_id.into(),
+            keys: keys
+                .iter()
+                .map(|k| (k.to_string(), k.to_string()))
+                .collect(),
+        }
+        .try_into()
+        .unwrap()
+    };
+
+    let tm = TokenManager::new(keyring("old", &["old"]));
+    let old = test_csrf_token(&tm);
 
-    assert!(!CsrfToken::validate_format(valid_header_invalid_payload))
+    tm.set_keyring(keyring("new", &["old", "new"]));
+    let new = test_csrf_token(&tm);
+    assert!(tm.decode_csrf_token(&old).is_ok());
+    assert!(tm.decode_csrf_token(&new).is_ok());
+
+    tm.set_keyring(keyring("new", &["new"]));
+    assert!(tm.decode_csrf_token(&old).is_err());
+    assert!(tm.decode_csrf_token(&new).is_ok());
+}
+
+#[test]
+fn csrf_token_refresh_chain_is_capped() {
+    let tm = TokenManager::new(Keyring::from_secret(""));
+    let headers = test_csrf_token(&tm);
+    let mut token = tm.decode_csrf_token(&headers).unwrap();
+
+    let refreshed = tm.refresh_csrf_token(&token).unwrap();
+    let headers = HeaderMap::from_iter([(CsrfToken::header_name(), refreshed.parse().unwrap())]);
+    assert_eq!(
+        tm.decode_csrf_token(&headers).unwrap().oiat,
+        Some(token.iat)
+    );
+
+    token.oiat = Some(token.iat - TOKEN_MAX_AGE_SECS);
+    assert!(matches!(
+        tm.refresh_csrf_token(&token),
+        Err(CsrfTokenError::MaxAgeExceeded)
+    ));
+}
+
+#[test]
+fn csrf_validation_checks_origin() {
+    let tm = TokenManager::new(Keyring::from_secret(""));
+    let token = tm.decode_csrf_token(&test_csrf_token(&tm)).unwrap();
+    let (domains, config) = (
+        [Domain::from("walletconnect.com".to_owned())],
+        VerifyConfig::default(),
+    );
+
+    assert!(token
+        .check_origin("https://app.walletconnect.com", &domains, &config)
+        .is_ok());
+    assert!(matches!(
+        token.check_origin("https://evil.com", &domains, &config),
+        Err(CsrfTokenError::OriginMismatch)
+    ));
+}
+
+#[test]
+fn test_is_origin_allowed() {
+    fn case_with_config(origin: &str, domains: &[&str], config: &VerifyConfig, expected: bool) {
+        let domains: Vec<_> = domains
+            .iter()
+            .map(|s| Domain::from(s.to_string()))
+            .collect();
+        assert_eq!(
+            is_origin_allowed(origin, &domains, config),
+            expected,
+            "{origin}"
+        );
+    }
+
+    fn case(origin: &str, domains: &[&str], expected: bool) {
+        case_with_config(origin, domains, &VerifyConfig::default(), expected)
+    }
+
+    let domains = &["walletconnect.com", "localhost"];
+
+    case("https://walletconnect.com", domains, true);
+    case("https://app.walletconnect.com", domains, true);
+    case("http://walletconnect.com", domains, false);
+    case("https://walletconnect.com:8443", domains, false);
+    case("https://evilwalletconnect.com", domains, false);
+    case("https://walletconnect.com.evil.com", domains, false);
+    case("http://localhost:3000", &[], true);
+    case("https://app.localhost", &[], true);
+    case("http://127.0.0.1:8080", &[], true);
+    case("null", domains, false);
+    case("", domains, false);
+
+    let config = VerifyConfig {
+        allowed_schemes: vec!["http".into()],
+        allow_localhost: false,
+        ..Default::default()
+    };
+    case_with_config("http://walletconnect.com", domains, &config, true);
+    case_with_config("https://walletconnect.com", domains, &config, false);
+    case_with_config("http://localhost:3000", &[], &config, false);
 }
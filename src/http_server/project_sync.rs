@@ -0,0 +1,61 @@
+use {
+    super::State,
+    crate::{
+        project_registry::sync::{Synced, Update},
+        util::{signer::RequestSigner, time::unix_timestamp},
+    },
+    axum::{
+        body::Bytes,
+        http::{HeaderMap, Method, StatusCode, Uri},
+    },
+    metrics::counter,
+    tracing::{instrument, warn},
+};
+
+/// Webhook receiving [`Update`]s of a [`Synced`] project registry, signed the
+/// same way as the requests to the Cloudflare KV worker.
+#[derive(Clone)]
+pub struct ProjectSyncWebhook {
+    pub registry: Synced,
+    pub signer: RequestSigner,
+}
+
+#[instrument(level = "debug", skip_all)]
+pub(super) async fn post<S, G>(
+    s: State<S, G>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    let Some(webhook) = &s.project_sync else {
+        return StatusCode::NOT_FOUND;
+    };
+
+    if let Err(e) = webhook
+        .signer
+        .verify(&method, uri.path(), &body, &headers, unix_timestamp())
+    {
+        counter!("project_registry_webhook_rejections", 1);
+        warn!("Project sync webhook: {e}");
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    let update = match serde_json::from_slice::<Update>(&body) {
+        Ok(update) => update,
+        Err(e) => {
+            counter!("project_registry_webhook_rejections", 1);
+            warn!("Project sync webhook: malformed update: {e}");
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+
+    match webhook.registry.apply(update) {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(e) => {
+            counter!("project_registry_webhook_rejections", 1);
+            warn!("Project sync webhook: {e:?}");
+            StatusCode::UNPROCESSABLE_ENTITY
+        }
+    }
+}
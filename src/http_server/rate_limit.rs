@@ -0,0 +1,408 @@
+use {
+    super::ClientIp,
+    crate::{util::redis, ProjectId},
+    async_trait::async_trait,
+    axum::{
+        extract::{MatchedPath, Request, State},
+        middleware::Next,
+        response::{IntoResponse, Response},
+    },
+    hyper::{header, StatusCode},
+    metrics::counter,
+    serde::Deserialize,
+    sha2::{Digest as _, Sha256},
+    std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+    tracing::warn,
+};
+
+pub type Error = anyhow::Error;
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Token bucket: allows bursts of `burst` requests, refilled at
+/// `per_minute`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Limit {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+impl Limit {
+    fn refill_per_sec(&self) -> f64 {
+        f64::from(self.per_minute) / 60.0
+    }
+}
+
+/// Per-route limits, a limit is disabled if its `per_minute` is `0`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// `GET /:project_id`, per client IP.
+    pub enclave_ip_burst: u32,
+    pub enclave_ip_per_minute: u32,
+
+    /// `GET /:project_id`, per project.
+    pub enclave_project_burst: u32,
+    pub enclave_project_per_minute: u32,
+
+    /// `POST /attestation`, per client IP.
+    pub attestation_ip_burst: u32,
+    pub attestation_ip_per_minute: u32,
+
+    /// `POST /attestation`, per CSRF token.
+    pub attestation_token_burst: u32,
+    pub attestation_token_per_minute: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enclave_ip_burst: 60,
+            enclave_ip_per_minute: 120,
+            enclave_project_burst: 6000,
+            enclave_project_per_minute: 60000,
+            attestation_ip_burst: 30,
+            attestation_ip_per_minute: 60,
+            attestation_token_burst: 10,
+            attestation_token_per_minute: 20,
+        }
+    }
+}
+
+impl Config {
+    fn limit(&self, route: Route, key: KeyKind) -> Option<Limit> {
+        let (burst, per_minute) = match (route, key) {
+            (Route::Enclave, KeyKind::Ip) => (self.enclave_ip_burst, self.enclave_ip_per_minute),
+            (Route::Enclave, KeyKind::Project) => {
+                (self.enclave_project_burst, self.enclave_project_per_minute)
+            }
+            (Route::Attestation, KeyKind::Ip) => {
+                (self.attestation_ip_burst, self.attestation_ip_per_minute)
+            }
+            (Route::Attestation, KeyKind::Token) => (
+                self.attestation_token_burst,
+                self.attestation_token_per_minute,
+            ),
+            _ => return None,
+        };
+
+        (per_minute > 0).then_some(Limit {
+            burst: burst.max(1),
+            per_minute,
+        })
+    }
+}
+
+/// Rate limited route of the public server.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Route {
+    Enclave,
+    Attestation,
+}
+
+impl Route {
+    fn new(path: Option<&MatchedPath>, method: &hyper::Method) -> Option<Self> {
+        match (path.map(MatchedPath::as_str), method) {
+            (Some("/:project_id"), &hyper::Method::GET) => Some(Self::Enclave),
+            (Some("/attestation"), &hyper::Method::POST) => Some(Self::Attestation),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Enclave => "enclave",
+            Self::Attestation => "attestation",
+        }
+    }
+}
+
+/// What a request is rate limited by.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum KeyKind {
+    Ip,
+    Project,
+    Token,
+}
+
+impl KeyKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Ip => "ip",
+            Self::Project => "project",
+            Self::Token => "token",
+        }
+    }
+}
+
+/// Storage of the token buckets.
+#[async_trait]
+pub trait Storage: Send + Sync + 'static {
+    /// Takes a token from every bucket of the `keys` if none of them is empty,
+    /// otherwise returns how long to wait for all of them to have one.
+    async fn take(&self, keys: &[(String, Limit)]) -> Result<Option<Duration>>;
+}
+
+/// [`Storage`] keeping the buckets in process memory, so the limits apply
+/// per instance.
+#[derive(Default)]
+pub struct Memory {
+    state: Mutex<MemoryState>,
+}
+
+struct MemoryState {
+    buckets: HashMap<String, Bucket>,
+    pruned_at: Instant,
+}
+
+impl Default for MemoryState {
+    fn default() -> Self {
+        Self {
+            buckets: HashMap::new(),
+            pruned_at: Instant::now(),
+        }
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+    full_at: Instant,
+}
+
+/// How often the full buckets get dropped.
+const MEMORY_PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[async_trait]
+impl Storage for Memory {
+    async fn take(&self, keys: &[(String, Limit)]) -> Result<Option<Duration>> {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+
+        // A full bucket is the same as a missing one.
+        if now.duration_since(state.pruned_at) >= MEMORY_PRUNE_INTERVAL {
+            state.buckets.retain(|_, bucket| bucket.full_at > now);
+            state.pruned_at = now;
+        }
+
+        let mut wait = None;
+        for (key, limit) in keys {
+            let (burst, rate) = (f64::from(limit.burst), limit.refill_per_sec());
+            let bucket = state.buckets.entry(key.clone()).or_insert(Bucket {
+                tokens: burst,
+                refilled_at: now,
+                full_at: now,
+            });
+
+            let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * rate).min(burst);
+            bucket.refilled_at = now;
+
+            if bucket.tokens < 1.0 {
+                wait = wait.max(Some(Duration::from_secs_f64((1.0 - bucket.tokens) / rate)));
+            }
+        }
+
+        for (key, limit) in keys {
+            let (burst, rate) = (f64::from(limit.burst), limit.refill_per_sec());
+            let bucket = state.buckets.get_mut(key).unwrap();
+
+            if wait.is_none() {
+                bucket.tokens -= 1.0;
+            }
+            bucket.full_at = now + Duration::from_secs_f64((burst - bucket.tokens) / rate);
+        }
+
+        Ok(wait)
+    }
+}
+
+/// Atomically refills the buckets and takes a token from each of them unless
+/// any is empty, returning the milliseconds to wait for all of them to have a
+/// token otherwise.
+///
+/// `ARGV` holds the burst and the refill rate per millisecond of every key.
+/// All the `KEYS` have to share a hash tag, so they hash to the same Redis
+/// Cluster slot.
+const TAKE_SCRIPT: &str = r"
+local time = redis.call('TIME')
+local now = time[1] * 1000 + math.floor(time[2] / 1000)
+
+local tokens = {}
+local wait = 0
+for i, key in ipairs(KEYS) do
+    local burst = tonumber(ARGV[2 * i - 1])
+    local refill_per_ms = tonumber(ARGV[2 * i])
+
+    local bucket = redis.call('HMGET', key, 'tokens', 'ts')
+    local t = tonumber(bucket[1]) or burst
+    local ts = tonumber(bucket[2]) or now
+    t = math.min(burst, t + math.max(0, now - ts) * refill_per_ms)
+
+    if t < 1 then
+        wait = math.max(wait, math.ceil((1 - t) / refill_per_ms))
+    end
+    tokens[i] = t
+end
+
+for i, key in ipairs(KEYS) do
+    local burst = tonumber(ARGV[2 * i - 1])
+    local refill_per_ms = tonumber(ARGV[2 * i])
+
+    if wait == 0 then
+        tokens[i] = tokens[i] - 1
+    end
+
+    redis.call('HSET', key, 'tokens', tostring(tokens[i]), 'ts', now)
+    redis.call('PEXPIRE', key, math.ceil(burst / refill_per_ms))
+end
+
+return wait
+";
+
+/// [`Storage`] keeping the buckets in Redis, so the limits are shared by
+/// every instance.
+#[async_trait]
+impl Storage for redis::Adapter {
+    async fn take(&self, keys: &[(String, Limit)]) -> Result<Option<Duration>> {
+        let (keys, args): (Vec<_>, Vec<_>) = keys
+            .iter()
+            .map(|(key, limit)| (key.as_str(), (limit.burst, limit.refill_per_sec() / 1000.0)))
+            .unzip();
+
+        let wait: u64 = self.eval(TAKE_SCRIPT, &keys, args).await?;
+        Ok((wait > 0).then(|| Duration::from_millis(wait)))
+    }
+}
+
+/// Rate limits `GET /:project_id` and `POST /attestation` by the client IP,
+/// and by the project or the CSRF token respectively.
+pub struct RateLimiter {
+    storage: Box<dyn Storage>,
+    config: Config,
+}
+
+impl RateLimiter {
+    pub fn new(storage: impl Storage, config: Config) -> Self {
+        Self {
+            storage: Box::new(storage),
+            config,
+        }
+    }
+
+    /// Returns how long to wait before retrying if any of the limits of the
+    /// request is exceeded.
+    ///
+    /// The limits are checked all at once, so a request rejected by one of
+    /// them doesn't use up the others. Storage errors don't block the
+    /// requests.
+    async fn check(&self, route: Route, keys: &[(KeyKind, Option<String>)]) -> Option<Duration> {
+        let keys: Vec<_> = keys
+            .iter()
+            .filter_map(|(kind, value)| {
+                let (value, limit) = (value.as_ref()?, self.config.limit(route, *kind)?);
+                // The keys of a route share a hash tag, so they can be taken from at once.
+                let key = format!(
+                    "{{rate_limit:{}}}:{}:{value}",
+                    route.as_str(),
+                    kind.as_str()
+                );
+                Some((key, limit))
+            })
+            .collect();
+
+        if keys.is_empty() {
+            return None;
+        }
+
+        match self.storage.take(&keys).await {
+            Ok(None) => None,
+            Ok(Some(wait)) => {
+                counter!("rate_limit_rejections", 1, "route" => route.as_str());
+                Some(wait)
+            }
+            Err(e) => {
+                counter!("rate_limit_errors", 1, "route" => route.as_str());
+                warn!("Failed to check rate limit: {e:?}");
+                None
+            }
+        }
+    }
+}
+
+pub(super) async fn middleware(
+    State(limiter): State<Arc<RateLimiter>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let Some(route) = Route::new(req.extensions().get::<MatchedPath>(), req.method()) else {
+        return next.run(req).await;
+    };
+
+    let ip = req
+        .extensions()
+        .get::<ClientIp>()
+        .map(|ClientIp(ip)| ip.to_string());
+
+    let keys = match route {
+        Route::Enclave => {
+            let path = req.uri().path().trim_start_matches('/');
+            let project_id = path.parse::<ProjectId>().is_ok().then(|| path.to_owned());
+            [(KeyKind::Ip, ip), (KeyKind::Project, project_id)]
+        }
+        Route::Attestation => {
+            // Hashing, as the tokens are too long to be used as keys as is.
+            let token = req
+                .headers()
+                .get(super::CsrfToken::header_name())
+                .map(|token| format!("{:x}", Sha256::digest(token.as_bytes())));
+            [(KeyKind::Ip, ip), (KeyKind::Token, token)]
+        }
+    };
+
+    match limiter.check(route, &keys).await {
+        None => next.run(req).await,
+        Some(wait) => {
+            let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+                "Too many requests",
+            )
+                .into_response()
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_memory() {
+    let storage = Memory::default();
+    let limit = Limit {
+        burst: 2,
+        per_minute: 60,
+    };
+    let keys = |keys: &[&str]| -> Vec<_> { keys.iter().map(|k| (k.to_string(), limit)).collect() };
+
+    assert_eq!(storage.take(&keys(&["a"])).await.unwrap(), None);
+    assert_eq!(storage.take(&keys(&["a"])).await.unwrap(), None);
+
+    let wait = storage.take(&keys(&["a"])).await.unwrap().unwrap();
+    assert!(wait > Duration::from_millis(900) && wait <= Duration::from_secs(1));
+
+    // Buckets are independent.
+    assert_eq!(storage.take(&keys(&["b"])).await.unwrap(), None);
+
+    // Nothing is taken unless every bucket has a token.
+    assert!(storage.take(&keys(&["a", "b"])).await.unwrap().is_some());
+    assert_eq!(storage.take(&keys(&["b"])).await.unwrap(), None);
+
+    let config = Config {
+        attestation_token_per_minute: 0,
+        ..Default::default()
+    };
+    assert_eq!(config.limit(Route::Attestation, KeyKind::Token), None);
+    assert_eq!(config.limit(Route::Enclave, KeyKind::Token), None);
+}
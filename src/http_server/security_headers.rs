@@ -0,0 +1,206 @@
+use {
+    axum::{
+        extract::{MatchedPath, Request, State},
+        middleware::Next,
+        response::Response,
+    },
+    hyper::{
+        header,
+        http::{HeaderName, HeaderValue},
+        HeaderMap,
+    },
+    serde::Deserialize,
+    std::sync::Arc,
+    tracing::warn,
+};
+
+/// Security headers added to every response of the public server.
+///
+/// Headers already set by a handler are never overwritten, so handlers
+/// producing a more specific policy (e.g. the enclave
+/// `Content-Security-Policy`) take precedence.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct SecurityHeaders {
+    pub referrer_policy: String,
+    pub permissions_policy: String,
+
+    /// `Cross-Origin-Resource-Policy` of the enclave document, which gets
+    /// embedded cross-origin by dApps.
+    pub enclave_cross_origin_resource_policy: String,
+
+    /// `Cross-Origin-Resource-Policy` of every other response.
+    pub cross_origin_resource_policy: String,
+
+    pub enclave_cache_control: String,
+    pub script_cache_control: String,
+    pub attestation_cache_control: String,
+    pub default_cache_control: String,
+}
+
+impl Default for SecurityHeaders {
+    fn default() -> Self {
+        Self {
+            referrer_policy: "no-referrer".into(),
+            permissions_policy: "accelerometer=(), camera=(), geolocation=(), gyroscope=(), \
+                                 magnetometer=(), microphone=(), payment=(), usb=()"
+                .into(),
+            enclave_cross_origin_resource_policy: "cross-origin".into(),
+            cross_origin_resource_policy: "same-origin".into(),
+            enclave_cache_control: "no-store".into(),
+            script_cache_control: "no-cache".into(),
+            attestation_cache_control: "no-store".into(),
+            default_cache_control: "no-store".into(),
+        }
+    }
+}
+
+/// `Content-Security-Policy` of the responses not being HTML documents.
+pub(super) const DEFAULT_CONTENT_SECURITY_POLICY: &str =
+    "default-src 'none'; frame-ancestors 'none'";
+
+/// Route of the public server, as far as the security headers are concerned.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Route {
+    Enclave,
+    Script,
+    Attestation,
+    Other,
+}
+
+impl Route {
+    fn from_matched_path(path: Option<&MatchedPath>) -> Self {
+        match path.map(MatchedPath::as_str) {
+            Some("/:project_id") => Self::Enclave,
+            Some("/index.js") => Self::Script,
+            Some(p) if p.starts_with("/attestation") => Self::Attestation,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Builds the `Content-Security-Policy` of the enclave document.
+///
+/// Only the script having the provided `nonce` is allowed to be executed and
+/// it's only allowed to talk back to this server.
+pub(super) fn enclave_content_security_policy(nonce: &str, frame_ancestors: &str) -> String {
+    format!(
+        "default-src 'none'; script-src 'nonce-{nonce}'; connect-src 'self'; base-uri 'none'; \
+         form-action 'none'; {frame_ancestors}"
+    )
+}
+
+impl SecurityHeaders {
+    fn apply(&self, route: Route, headers: &mut HeaderMap) {
+        let (cache_control, corp) = match route {
+            Route::Enclave => (
+                &self.enclave_cache_control,
+                &self.enclave_cross_origin_resource_policy,
+            ),
+            Route::Script => (
+                &self.script_cache_control,
+                &self.cross_origin_resource_policy,
+            ),
+            Route::Attestation => (
+                &self.attestation_cache_control,
+                &self.cross_origin_resource_policy,
+            ),
+            Route::Other => (
+                &self.default_cache_control,
+                &self.cross_origin_resource_policy,
+            ),
+        };
+
+        let pairs = [
+            (
+                header::CONTENT_SECURITY_POLICY,
+                DEFAULT_CONTENT_SECURITY_POLICY,
+            ),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+            (header::REFERRER_POLICY, &self.referrer_policy),
+            (permissions_policy(), &self.permissions_policy),
+            (cross_origin_resource_policy(), corp),
+            (header::CACHE_CONTROL, cache_control),
+        ];
+
+        for (name, value) in pairs {
+            if headers.contains_key(&name) || value.is_empty() {
+                continue;
+            }
+
+            match HeaderValue::from_str(value) {
+                Ok(value) => {
+                    headers.insert(name, value);
+                }
+                Err(_) => warn!("Invalid value of the {name} header: {value}"),
+            };
+        }
+    }
+}
+
+// Using const values instead of fns produces this warning:
+// https://rust-lang.github.io/rust-clippy/master/index.html#declare_interior_mutable_const
+const fn permissions_policy() -> HeaderName {
+    HeaderName::from_static("permissions-policy")
+}
+
+const fn cross_origin_resource_policy() -> HeaderName {
+    HeaderName::from_static("cross-origin-resource-policy")
+}
+
+/// Generates a random nonce, e.g. to be used in a `script-src` directive.
+pub(super) fn nonce() -> String {
+    format!("{:032x}", rand::random::<u128>())
+}
+
+pub(super) async fn middleware(
+    State(policy): State<Arc<SecurityHeaders>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let route = Route::from_matched_path(req.extensions().get::<MatchedPath>());
+    let mut resp = next.run(req).await;
+    policy.apply(route, resp.headers_mut());
+    resp
+}
+
+#[test]
+fn test_security_headers() {
+    let policy = SecurityHeaders {
+        enclave_cache_control: "no-cache".into(),
+        ..Default::default()
+    };
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_SECURITY_POLICY,
+        HeaderValue::from_static("custom"),
+    );
+    policy.apply(Route::Enclave, &mut headers);
+
+    assert_eq!(headers[header::CONTENT_SECURITY_POLICY], "custom");
+    assert_eq!(headers[header::CACHE_CONTROL], "no-cache");
+    assert_eq!(headers[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+    assert_eq!(headers[header::REFERRER_POLICY], "no-referrer");
+    assert_eq!(headers[cross_origin_resource_policy()], "cross-origin");
+    assert!(headers.contains_key(permissions_policy()));
+
+    let mut headers = HeaderMap::new();
+    policy.apply(Route::Attestation, &mut headers);
+
+    assert_eq!(
+        headers[header::CONTENT_SECURITY_POLICY],
+        DEFAULT_CONTENT_SECURITY_POLICY
+    );
+    assert_eq!(headers[header::CACHE_CONTROL], "no-store");
+    assert_eq!(headers[cross_origin_resource_policy()], "same-origin");
+}
+
+#[test]
+fn test_enclave_content_security_policy() {
+    assert_eq!(
+        enclave_content_security_policy("abc", "frame-ancestors https://walletconnect.com"),
+        "default-src 'none'; script-src 'nonce-abc'; connect-src 'self'; base-uri 'none'; \
+         form-action 'none'; frame-ancestors https://walletconnect.com"
+    );
+}
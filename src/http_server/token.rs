@@ -0,0 +1,49 @@
+use {
+    super::{enabled_project, Command, CsrfToken, RequestInfo, RequestKind, State},
+    crate::{GetVerifyStatus, GetVerifyStatusResult, Handle},
+    axum::{
+        extract::Json,
+        response::{IntoResponse, Response},
+    },
+    hyper::HeaderMap,
+    serde::Serialize,
+    tracing::instrument,
+};
+
+#[derive(Debug, Serialize)]
+pub(super) struct RefreshBody {
+    token: String,
+}
+
+/// Exchanges a still valid CSRF token for a new one issued for the same
+/// project, so the enclave doesn't need to be reloaded when its token is about
+/// to expire.
+///
+/// The project is checked again, so the new token isn't issued at all if
+/// Verify got disabled.
+#[instrument(level = "debug", skip_all)]
+pub(super) async fn refresh<S, G>(
+    s: State<S, G>,
+    headers: HeaderMap,
+    request_info: RequestInfo,
+) -> Result<impl IntoResponse, Response>
+where
+    S: for<'a> Handle<Command<GetVerifyStatus<'a>>, Result = GetVerifyStatusResult>,
+{
+    let claims = s
+        .token_manager
+        .decode_csrf_token(&headers)
+        .map_err(IntoResponse::into_response)?;
+
+    enabled_project(&s, &claims, request_info, RequestKind::TokenRefresh).await?;
+
+    let token = s
+        .token_manager
+        .refresh_csrf_token(&claims)
+        .map_err(IntoResponse::into_response)?;
+
+    Ok((
+        [(CsrfToken::header_name(), token.clone())],
+        Json(RefreshBody { token }),
+    ))
+}
@@ -3,7 +3,11 @@ use {
     arrayvec::ArrayString,
     derive_more::{AsRef, From},
     serde::{Deserialize, Serialize},
-    std::time::Duration,
+    std::{
+        str::FromStr,
+        sync::Arc,
+        time::{Duration, Instant},
+    },
     tap::{Tap, TapFallible, TapOptional},
     tracing::{error, instrument, warn},
     wc::future::FutureExt as _,
@@ -11,7 +15,7 @@ use {
 pub use {
     anyhow::Error,
     async_trait::async_trait,
-    attestation_store::AttestationStore,
+    attestation_store::{AttestationStore, Ttl},
     event_sink::EventSink,
     project_registry::ProjectRegistry,
     scam_guard::ScamGuard,
@@ -37,21 +41,85 @@ pub trait Handle<Cmd>: Send + Sync + 'static {
 #[derive(Debug, Clone, Copy)]
 pub struct GetVerifyStatus<'a> {
     pub project_id: &'a ProjectId,
+
+    /// Location of the request, if known.
+    pub location: Option<&'a Location>,
+}
+
+/// Geolocation of a request.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Location {
+    /// ISO 3166-1 alpha-2 code.
+    pub country: Arc<str>,
+
+    /// ISO 3166-2 codes of the subdivisions, without the country prefix.
+    pub subdivisions: Vec<String>,
+}
+
+impl Location {
+    /// Checks whether the location matches a `CC` (country) or `CC-SUB`
+    /// (subdivision of a country) region, e.g. `UA` or `UA-43`.
+    pub fn is_in(&self, region: &str) -> bool {
+        match region.split_once('-') {
+            Some((country, subdivision)) => {
+                country.eq_ignore_ascii_case(&self.country)
+                    && self
+                        .subdivisions
+                        .iter()
+                        .any(|s| s.eq_ignore_ascii_case(subdivision))
+            }
+            None => region.eq_ignore_ascii_case(&self.country),
+        }
+    }
 }
 
 /// Status of the Verify API of some project.
 #[derive(Debug)]
 pub enum VerifyStatus {
-    /// Verify API is disabled.
+    /// Verify API is disabled in the project settings.
     Disabled,
 
+    /// Verify API is enabled, but the project has no verified domains.
+    NoVerifiedDomains,
+
+    /// Verify API is disabled for the country of the request by the project
+    /// settings.
+    DisabledInCountry,
+
     /// Verify API is enabled.
     Enabled {
         /// List of the verified domains of the project.
         verified_domains: Vec<Domain>,
+
+        config: VerifyConfig,
     },
 }
 
+impl VerifyStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Disabled => "disabled",
+            Self::NoVerifiedDomains => "no_verified_domains",
+            Self::DisabledInCountry => "disabled_in_country",
+            Self::Enabled { .. } => "enabled",
+        }
+    }
+
+    /// Explains why the Verify API is (not) served.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Disabled => "Verify API is disabled in the project settings",
+            Self::NoVerifiedDomains => {
+                "Verify API is enabled, but the project has no verified domains"
+            }
+            Self::DisabledInCountry => {
+                "Verify API is disabled for the country of the request by the project settings"
+            }
+            Self::Enabled { .. } => "Verify API is enabled",
+        }
+    }
+}
+
 /// Error of getting a [`VerifyStatus`] via [`Bouncer::get_verify_status`].
 #[derive(Debug, thiserror::Error)]
 pub enum GetVerifyStatusError {
@@ -65,10 +133,53 @@ pub enum GetVerifyStatusError {
 #[derive(AsRef, Clone, Debug, From, Serialize, Deserialize)]
 pub struct Domain(String);
 
-#[derive(AsRef, Clone, Copy, Debug)]
+impl Domain {
+    /// Checks that the domain is a lowercase hostname without a scheme, port
+    /// or path.
+    pub fn is_valid(&self) -> bool {
+        let Ok(url) = reqwest::Url::parse(&format!("https://{}", self.0)) else {
+            return false;
+        };
+
+        url.host_str() == Some(&self.0) && url.port().is_none() && url.path() == "/"
+    }
+
+    /// Normalizes the domain as entered in the project settings, making it
+    /// lowercase and stripping the scheme, the wildcard and the trailing dots
+    /// and slashes. `None` if it's not valid even then.
+    pub fn normalized(&self) -> Option<Self> {
+        let domain = self.0.trim().to_lowercase();
+        let domain = domain
+            .strip_prefix("http://")
+            .or_else(|| domain.strip_prefix("https://"))
+            .unwrap_or(&domain);
+        let domain = domain.strip_prefix("*.").unwrap_or(domain);
+
+        let domain = Self(domain.trim_end_matches(['/', '.']).to_owned());
+        domain.is_valid().then_some(domain)
+    }
+}
+
+#[derive(AsRef, Clone, Copy, Debug, Serialize, Eq, Hash, PartialEq)]
 #[as_ref(forward)]
 pub struct ProjectId(ArrayString<32>);
 
+impl FromStr for ProjectId {
+    type Err = InvalidProjectId;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ArrayString::<32>::from(s)
+            .ok()
+            .filter(|s| s.len() == 32 && !s.chars().any(|c| !c.is_ascii_hexdigit()))
+            .map(Self)
+            .ok_or(InvalidProjectId)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("ProjectId should be a hex string 32 chars long")]
+pub struct InvalidProjectId;
+
 impl<'de> Deserialize<'de> for ProjectId {
     fn deserialize<D>(de: D) -> Result<Self, D::Error>
     where
@@ -78,11 +189,8 @@ impl<'de> Deserialize<'de> for ProjectId {
 
         ArrayString::<32>::deserialize(de)
             .ok()
-            .filter(|s| s.len() == 32 && !s.chars().any(|c| !c.is_ascii_hexdigit()))
-            .map(Self)
-            .ok_or(D::Error::custom(
-                "ProjectId should be a hex string 32 chars long",
-            ))
+            .and_then(|s| s.parse().ok())
+            .ok_or(D::Error::custom(InvalidProjectId))
     }
 }
 
@@ -90,9 +198,108 @@ impl<'de> Deserialize<'de> for ProjectId {
 pub struct ProjectData {
     pub is_verify_enabled: bool,
     pub verified_domains: Vec<Domain>,
+
+    /// Defaulted, so the projects cached before it was introduced are still
+    /// readable.
+    #[serde(default)]
+    pub verify_config: VerifyConfig,
+
+    /// Unix timestamp of when the data was fetched from the registry, if
+    /// known. Kept by the caches, so it tells the age of cached data.
+    #[serde(default)]
+    pub fetched_at: Option<u64>,
+}
+
+/// Per-project configuration of the Verify API.
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(default)]
+pub struct VerifyConfig {
+    /// Schemes the origins of the verified domains are allowed to use.
+    pub allowed_schemes: Vec<String>,
+
+    /// Whether `localhost` and `127.0.0.1` origins are allowed regardless of
+    /// the verified domains.
+    pub allow_localhost: bool,
+
+    /// For how long the attestations are served, the default of the
+    /// attestation store if not set.
+    pub attestation_ttl_secs: Option<u64>,
+
+    pub scam_check: ScamCheck,
+
+    /// Regions the enclave isn't served to, either ISO 3166-1 alpha-2 country
+    /// codes or ISO 3166-2 subdivision codes, see [`Location::is_in`].
+    pub disabled_countries: Vec<String>,
+}
+
+impl Default for VerifyConfig {
+    fn default() -> Self {
+        Self {
+            allowed_schemes: vec!["https".into()],
+            allow_localhost: true,
+            attestation_ttl_secs: None,
+            scam_check: ScamCheck::default(),
+            disabled_countries: vec![],
+        }
+    }
+}
+
+impl VerifyConfig {
+    pub fn attestation_ttl(&self) -> Option<Duration> {
+        self.attestation_ttl_secs.map(Duration::from_secs)
+    }
+
+    pub fn is_location_disabled(&self, location: &Location) -> bool {
+        self.disabled_countries
+            .iter()
+            .any(|region| location.is_in(region))
+    }
+
+    /// Allowed schemes which aren't valid URL schemes, so can't be put into the
+    /// `Content-Security-Policy`.
+    pub fn invalid_schemes(&self) -> impl Iterator<Item = &str> {
+        self.allowed_schemes
+            .iter()
+            .map(String::as_str)
+            .filter(|scheme| !is_valid_scheme(scheme))
+    }
+}
+
+/// Checks that the `scheme` matches `[a-z][a-z0-9+.-]*`.
+pub fn is_valid_scheme(scheme: &str) -> bool {
+    let mut chars = scheme.chars();
+    chars.next().is_some_and(|c| c.is_ascii_lowercase())
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "+.-".contains(c))
+}
+
+/// How strictly the origins of the attestations are checked for being scams.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScamCheck {
+    /// Scam origins are only reported to the wallets via [`IsScam`].
+    #[default]
+    Report,
+
+    /// Attestations of scam origins are refused.
+    Block,
+}
+
+/// [`VerifyStatus`] of a project along with its data.
+#[derive(Debug)]
+pub struct ProjectVerifyStatus {
+    pub status: VerifyStatus,
+
+    /// Verified domains of the project, whatever the status.
+    pub verified_domains: Vec<Domain>,
+
+    /// Configuration of the project, whatever the status.
+    pub config: VerifyConfig,
+
+    /// See [`ProjectData::fetched_at`].
+    pub fetched_at: Option<u64>,
 }
 
-pub type GetVerifyStatusResult = Result<VerifyStatus, GetVerifyStatusError>;
+pub type GetVerifyStatusResult = Result<ProjectVerifyStatus, GetVerifyStatusError>;
 
 #[async_trait]
 impl<'a, I: Infra> Handle<GetVerifyStatus<'a>> for Service<I> {
@@ -100,22 +307,49 @@ impl<'a, I: Infra> Handle<GetVerifyStatus<'a>> for Service<I> {
 
     #[instrument(level = "warn", skip(self))]
     async fn handle(&self, cmd: GetVerifyStatus<'a>) -> Self::Result {
+        let deadline = self.deadline();
         let project_data = self
             .project_registry()
             .project_data(cmd.project_id)
-            .with_timeout(Duration::from_secs(1))
+            .with_timeout(deadline.timeout(self.timeouts.project_registry()))
             .await
             .context("ProjectRegistry::project_data timed out")?
             .tap_err(|e| error!("ProjectRegistry::project_data: {e:?}"))?
             .ok_or(GetVerifyStatusError::UnknownProject)
             .tap_err(|_| warn!("Unknown project id"))?;
 
-        let status = (project_data.is_verify_enabled && !project_data.verified_domains.is_empty())
-            .then_some(project_data.verified_domains)
-            .map(|verified_domains| VerifyStatus::Enabled { verified_domains })
-            .unwrap_or(VerifyStatus::Disabled);
+        let config = project_data.verify_config;
+        let is_country_disabled = cmd
+            .location
+            .is_some_and(|location| config.is_location_disabled(location));
+
+        // Enforced the same way they are reported by the diagnostics, the malformed
+        // ones never match.
+        let verified_domains: Vec<_> = project_data
+            .verified_domains
+            .iter()
+            .filter_map(Domain::normalized)
+            .collect();
+
+        let status = if !project_data.is_verify_enabled {
+            VerifyStatus::Disabled
+        } else if verified_domains.is_empty() {
+            VerifyStatus::NoVerifiedDomains
+        } else if is_country_disabled {
+            VerifyStatus::DisabledInCountry
+        } else {
+            VerifyStatus::Enabled {
+                verified_domains,
+                config: config.clone(),
+            }
+        };
 
-        Ok(status)
+        Ok(ProjectVerifyStatus {
+            status,
+            verified_domains: project_data.verified_domains,
+            config,
+            fetched_at: project_data.fetched_at,
+        })
     }
 }
 
@@ -123,6 +357,12 @@ impl<'a, I: Infra> Handle<GetVerifyStatus<'a>> for Service<I> {
 pub struct SetAttestation<'a> {
     pub id: &'a str,
     pub origin: &'a str,
+
+    /// TTL of the attestation, the default of the attestation store if not
+    /// set.
+    pub ttl: Option<Duration>,
+
+    pub scam_check: ScamCheck,
 }
 
 #[derive(Debug)]
@@ -143,7 +383,17 @@ pub enum IsScam {
     Unknown,
 }
 
-pub type SetAttestationResult = anyhow::Result<()>;
+/// Error of handling a [`SetAttestation`] command.
+#[derive(Debug, thiserror::Error)]
+pub enum SetAttestationError {
+    #[error("Origin is a scam dApp")]
+    ScamOrigin,
+
+    #[error(transparent)]
+    Other(#[from] Error),
+}
+
+pub type SetAttestationResult = Result<(), SetAttestationError>;
 
 #[async_trait]
 impl<'a, I: Infra> Handle<SetAttestation<'a>> for Service<I> {
@@ -151,10 +401,23 @@ impl<'a, I: Infra> Handle<SetAttestation<'a>> for Service<I> {
 
     #[instrument(level = "debug", skip(self))]
     async fn handle(&self, cmd: SetAttestation<'a>) -> Self::Result {
+        let deadline = self.deadline();
+
+        if cmd.scam_check == ScamCheck::Block
+            && self.is_scam(cmd.origin, deadline).await == IsScam::Yes
+        {
+            warn!(origin = cmd.origin, "Attestation of a scam origin refused");
+            return Err(SetAttestationError::ScamOrigin);
+        }
+
         self.attestation_store()
-            .set_attestation(cmd.id, cmd.origin)
+            .set_attestation_with_ttl(cmd.id, cmd.origin, cmd.ttl.map_or(Ttl::Default, Ttl::After))
+            .with_timeout(deadline.timeout(self.timeouts.attestation_store()))
             .await
-            .tap_err(|e| error!("AttestationStore::set_attestation: {e:?}"))
+            .context("AttestationStore::set_attestation timed out")?
+            .tap_err(|e| error!("AttestationStore::set_attestation: {e:?}"))?;
+
+        Ok(())
     }
 }
 
@@ -171,25 +434,20 @@ impl<'a, I: Infra> Handle<GetAttestation<'a>> for Service<I> {
 
     #[instrument(level = "debug", skip(self))]
     async fn handle(&self, cmd: GetAttestation<'a>) -> Self::Result {
+        let deadline = self.deadline();
         let origin = self
             .attestation_store()
             .get_attestation(cmd.id)
+            .with_timeout(deadline.timeout(self.timeouts.attestation_store()))
             .await
+            .context("AttestationStore::get_attestation timed out")?
             .tap_err(|e| error!("AttestationStore::get_attestation: {e:?}"))?;
 
         let Some(origin) = origin else {
             return Ok(None);
         };
 
-        let is_scam = self
-            .scam_guard()
-            .is_scam(&origin)
-            .with_timeout(Duration::from_secs(10))
-            .await
-            .map_err(|_| error!("ScamGuard::is_scam timed out"))
-            .ok()
-            .and_then(|res| res.map_err(|e| error!("ScamGuard::is_scam: {e:?}")).ok())
-            .unwrap_or(IsScam::Unknown);
+        let is_scam = self.is_scam(&origin, deadline).await;
 
         Ok(Some(Attestation { origin, is_scam }))
     }
@@ -197,11 +455,23 @@ impl<'a, I: Infra> Handle<GetAttestation<'a>> for Service<I> {
 
 pub struct Service<I> {
     infra: I,
+    timeouts: Timeouts,
 }
 
 impl<I> Service<I> {
     pub fn new(infra: I) -> Service<I> {
-        Service { infra }
+        Service {
+            infra,
+            timeouts: Timeouts::default(),
+        }
+    }
+
+    pub fn with_timeouts(self, timeouts: Timeouts) -> Self {
+        Self { timeouts, ..self }
+    }
+
+    fn deadline(&self) -> Deadline {
+        Deadline(Instant::now() + self.timeouts.request_budget())
     }
 
     pub fn observable<E>(self, event_sink: Option<E>) -> Observable<Self, E> {
@@ -212,6 +482,66 @@ impl<I> Service<I> {
     }
 }
 
+/// Timeouts of the calls to the [`Infra`] dependencies, in milliseconds.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Timeouts {
+    pub project_registry_ms: u64,
+    pub attestation_store_ms: u64,
+    pub scam_guard_ms: u64,
+    pub analytics_export_ms: u64,
+
+    /// Total time budget of handling a single command, bounding its latency
+    /// regardless of how many dependencies it calls.
+    pub request_budget_ms: u64,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            project_registry_ms: 1000,
+            attestation_store_ms: 2000,
+            scam_guard_ms: 10_000,
+            analytics_export_ms: 30_000,
+            request_budget_ms: 5000,
+        }
+    }
+}
+
+impl Timeouts {
+    pub fn project_registry(&self) -> Duration {
+        Duration::from_millis(self.project_registry_ms)
+    }
+
+    pub fn attestation_store(&self) -> Duration {
+        Duration::from_millis(self.attestation_store_ms)
+    }
+
+    pub fn scam_guard(&self) -> Duration {
+        Duration::from_millis(self.scam_guard_ms)
+    }
+
+    pub fn analytics_export(&self) -> Duration {
+        Duration::from_millis(self.analytics_export_ms)
+    }
+
+    pub fn request_budget(&self) -> Duration {
+        Duration::from_millis(self.request_budget_ms)
+    }
+}
+
+/// Point in time by which a command should be handled.
+#[derive(Clone, Copy, Debug)]
+struct Deadline(Instant);
+
+impl Deadline {
+    /// Returns the timeout of a dependency call, capped by the time left
+    /// until the deadline.
+    fn timeout(&self, max: Duration) -> Duration {
+        max.min(self.0.saturating_duration_since(Instant::now()))
+    }
+}
+
 /// Infrastucture dependencies of this service.
 pub trait Infra: Send + Sync + 'static {
     type AttestationStore: AttestationStore;
@@ -258,6 +588,19 @@ impl<I: Infra> Service<I> {
     pub fn scam_guard(&self) -> &I::ScamGuard {
         self.infra.scam_guard()
     }
+
+    /// Checks the `origin` via the [`ScamGuard`], falling back to
+    /// [`IsScam::Unknown`] if the check fails.
+    async fn is_scam(&self, origin: &str, deadline: Deadline) -> IsScam {
+        self.scam_guard()
+            .is_scam(origin)
+            .with_timeout(deadline.timeout(self.timeouts.scam_guard()))
+            .await
+            .map_err(|_| error!("ScamGuard::is_scam timed out"))
+            .ok()
+            .and_then(|res| res.map_err(|e| error!("ScamGuard::is_scam: {e:?}")).ok())
+            .unwrap_or(IsScam::Unknown)
+    }
 }
 
 /// Command with an execution context attached to it.
@@ -8,29 +8,125 @@ use {
         AXUM_HTTP_REQUESTS_DURATION_SECONDS,
     },
     bouncer::{
-        attestation_store::{cf_kv::CloudflareKv, migration},
+        attestation_store::{
+            backfill,
+            cf_kv::CloudflareKv,
+            disk::{self, Disk},
+            memory::Memory,
+            replicated::{self, Replicated},
+            sql::{self, Sql},
+        },
         event_sink,
-        http_server::{RequestInfo, ServerConfig, TokenManager},
-        project_registry::{self, CachedExt as _},
-        scam_guard,
-        util::redis,
+        http_server::{
+            client_ip,
+            geoblock,
+            rate_limit,
+            KeyringConfig,
+            ProjectSyncWebhook,
+            RateLimiter,
+            Readiness,
+            RequestInfo,
+            RequestKind,
+            SecurityHeaders,
+            ServerConfig,
+            TokenManager,
+        },
+        project_registry::{self, sync::Synced, CachedExt as _, ProjectRegistry},
+        scam_guard::{self, ScamGuard},
+        util::{
+            geoip::{self, ReloadableResolver},
+            http,
+            redis,
+            signer::RequestSigner,
+        },
+        AttestationStore,
         GetAttestationHandled,
         GetVerifyStatusHandled,
         IsScam,
         SetAttestationHandled,
-        VerifyStatus,
+        Timeouts,
     },
     build_info::VersionControl,
+    clap::Parser as _,
     futures::{future::select, FutureExt},
     parquet_derive::ParquetRecordWriter,
+    reqwest::Url,
     serde::{Deserialize, Deserializer},
-    std::{future::Future, str::FromStr, sync::Arc},
+    std::{future::Future, path::PathBuf, str::FromStr, sync::Arc, time::Duration},
     tap::TapFallible,
     tokio::signal::unix::{signal, SignalKind},
-    tracing::info,
-    wc::geoip::MaxMindResolver,
+    tracing::{info, warn},
 };
 
+/// Which [`AttestationStore`] is used.
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AttestationStoreBackend {
+    /// Redis replicated into the Cloudflare KV worker.
+    #[default]
+    Replicated,
+
+    /// Process memory, for development.
+    Memory,
+
+    Redis,
+
+    /// Cloudflare KV worker.
+    CfKv,
+
+    /// SQLite or Postgres.
+    Sql,
+
+    /// Embedded database on the local disk, for single-node deployments.
+    Disk,
+}
+
+/// Which [`ProjectRegistry`] is used.
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectRegistryBackend {
+    /// WalletConnect Cloud registry.
+    #[default]
+    Cloud,
+
+    /// Local JSON, YAML or TOML file, see [`project_registry::file::File`].
+    File,
+
+    /// Every project kept in memory, synced via snapshots and webhook updates,
+    /// see [`project_registry::sync::Synced`].
+    Synced,
+}
+
+/// Which [`ScamGuard`] is used.
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ScamGuardBackend {
+    /// WalletConnect Data API.
+    #[default]
+    DataApi,
+
+    /// Fixed list of scam domains.
+    Static,
+
+    /// No scam checks.
+    None,
+}
+
+/// Where the rate limiting buckets are kept.
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitBackend {
+    /// Process memory, the limits apply per instance.
+    #[default]
+    Memory,
+
+    /// Redis, the limits are shared by every instance.
+    Redis,
+
+    /// No rate limiting.
+    None,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct Configuration {
     #[serde(default = "default_port")]
@@ -46,35 +142,241 @@ pub struct Configuration {
     #[serde(default)]
     pub log_pretty: bool,
 
-    pub attestation_cache_url: String,
+    #[serde(default)]
+    pub attestation_store_backend: AttestationStoreBackend,
+
+    /// Required by the `replicated` and `redis` attestation store backends.
+    pub attestation_cache_url: Option<String>,
+
+    #[serde(default)]
+    pub project_registry_backend: ProjectRegistryBackend,
+
+    /// Required by the `cloud` project registry backend.
+    pub project_registry_url: Option<String>,
+    pub project_registry_auth_token: Option<String>,
+    /// Projects aren't cached if not set.
+    pub project_registry_cache_url: Option<String>,
+
+    /// Required by the `file` project registry backend.
+    pub project_registry_file: Option<PathBuf>,
+
+    /// How often the projects file is checked for changes.
+    #[serde(default = "default_project_registry_file_reload_interval_secs")]
+    pub project_registry_file_reload_interval_secs: u64,
+
+    #[serde(default)]
+    pub scam_guard_backend: ScamGuardBackend,
+
+    /// Required by the `data_api` scam guard backend.
+    pub data_api_url: Option<String>,
+    pub data_api_auth_token: Option<String>,
+    /// Scam checks aren't cached if not set.
+    pub scam_guard_cache_url: Option<String>,
 
-    pub project_registry_url: String,
-    pub project_registry_auth_token: String,
-    pub project_registry_cache_url: String,
+    /// Scam domains of the `static` scam guard backend.
+    #[serde(default)]
+    pub scam_guard_static_domains: Vec<String>,
 
-    pub data_api_url: String,
-    pub data_api_auth_token: String,
-    pub scam_guard_cache_url: String,
+    #[serde(default)]
+    pub rate_limit_backend: RateLimitBackend,
 
-    pub cf_kv_endpoint: String,
+    /// Required by the `redis` rate limit backend.
+    pub rate_limit_redis_url: Option<String>,
 
-    pub secret: String,
+    /// Required by the `replicated` and `cf_kv` attestation store backends.
+    pub cf_kv_endpoint: Option<String>,
+    pub cf_kv_secret: Option<String>,
+
+    /// Legacy single secret, used if no keyring is configured.
+    pub secret: Option<String>,
 
     pub s3_endpoint: Option<String>,
 
     pub data_lake_bucket: Option<String>,
 
+    /// Local GeoIP database, used instead of the S3 one if set.
+    pub geoip_db_path: Option<PathBuf>,
     pub geoip_db_bucket: Option<String>,
     pub geoip_db_key: Option<String>,
 
+    /// How often the GeoIP database is reloaded, a local one only if it has
+    /// changed.
+    #[serde(default = "default_geoip_db_refresh_interval_secs")]
+    pub geoip_db_refresh_interval_secs: u64,
+
+    /// Merged into [`geoblock::Config::blocked_regions`].
+    #[serde(default)]
     pub blocked_countries: Vec<String>,
+
+    /// Loaded from the `SECURITY_HEADERS_` prefixed env variables.
+    #[serde(skip)]
+    pub security_headers: SecurityHeaders,
+
+    /// Loaded from the `GEOBLOCK_` prefixed env variables.
+    #[serde(skip)]
+    pub geoblock: geoblock::Config,
+
+    /// Loaded from the `CLIENT_IP_` prefixed env variables.
+    #[serde(skip)]
+    pub client_ip: client_ip::Config,
+
+    /// Loaded from the `KEYRING_` prefixed env variables.
+    #[serde(skip)]
+    pub keyring: KeyringConfig,
+    /// Outbound HTTP configs, loaded from the `CF_KV_HTTP_`, `DATA_API_HTTP_`
+    /// and `PROJECT_REGISTRY_HTTP_` prefixed env variables.
+    #[serde(skip)]
+    pub cf_kv_http: http::Config,
+    #[serde(skip)]
+    pub data_api_http: http::Config,
+    #[serde(skip)]
+    pub project_registry_http: http::Config,
+
+    /// Loaded from the `ATTESTATION_STORE_` prefixed env variables.
+    #[serde(skip)]
+    pub attestation_store_replication: replicated::Policy,
+
+    /// Loaded from the `ATTESTATION_STORE_SQL_` prefixed env variables.
+    #[serde(skip)]
+    pub attestation_store_sql: sql::Config,
+
+    /// Loaded from the `ATTESTATION_STORE_DISK_` prefixed env variables.
+    #[serde(skip)]
+    pub attestation_store_disk: disk::Config,
+
+    /// Loaded from the `PROJECT_REGISTRY_SYNC_` prefixed env variables.
+    #[serde(skip)]
+    pub project_registry_sync: project_registry::sync::Config,
+
+    /// Loaded from the `RATE_LIMIT_` prefixed env variables.
+    #[serde(skip)]
+    pub rate_limit: rate_limit::Config,
+
+    /// Loaded from the `TIMEOUTS_` prefixed env variables.
+    #[serde(skip)]
+    pub timeouts: Timeouts,
+}
+
+impl Configuration {
+    fn from_env() -> Result<Self, anyhow::Error> {
+        let mut config = envy::from_env::<Self>().context("Failed to build config")?;
+
+        config.security_headers = envy::prefixed("SECURITY_HEADERS_")
+            .from_env()
+            .context("Failed to build security headers config")?;
+
+        config.geoblock = envy::prefixed("GEOBLOCK_")
+            .from_env()
+            .context("Failed to build geoblock config")?;
+        config
+            .geoblock
+            .blocked_regions
+            .extend(config.blocked_countries.iter().cloned());
+
+        config.client_ip = envy::prefixed("CLIENT_IP_")
+            .from_env()
+            .context("Failed to build client IP config")?;
+
+        config.keyring = envy::prefixed("KEYRING_")
+            .from_env()
+            .context("Failed to build keyring config")?;
+
+        let http_config = |prefix: &str| {
+            envy::prefixed(prefix)
+                .from_env::<http::Config>()
+                .with_context(|| format!("Failed to build {prefix} config"))
+        };
+        config.cf_kv_http = http_config("CF_KV_HTTP_")?;
+        config.data_api_http = http_config("DATA_API_HTTP_")?;
+        config.project_registry_http = http_config("PROJECT_REGISTRY_HTTP_")?;
+
+        config.attestation_store_replication = envy::prefixed("ATTESTATION_STORE_")
+            .from_env()
+            .context("Failed to build attestation store replication config")?;
+
+        config.attestation_store_sql = envy::prefixed("ATTESTATION_STORE_SQL_")
+            .from_env()
+            .context("Failed to build SQL attestation store config")?;
+
+        config.attestation_store_disk = envy::prefixed("ATTESTATION_STORE_DISK_")
+            .from_env()
+            .context("Failed to build disk attestation store config")?;
+
+        config.project_registry_sync = envy::prefixed("PROJECT_REGISTRY_SYNC_")
+            .from_env()
+            .context("Failed to build project registry sync config")?;
+
+        config.rate_limit = envy::prefixed("RATE_LIMIT_")
+            .from_env()
+            .context("Failed to build rate limit config")?;
+
+        config.timeouts = envy::prefixed("TIMEOUTS_")
+            .from_env()
+            .context("Failed to build timeouts config")?;
+
+        Ok(config)
+    }
 }
 
 build_info::build_info!(fn build_info);
 
+/// Runs the server unless a command is provided.
+#[derive(clap::Parser)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Copies attestations from a Redis attestation store into another store.
+    MigrateAttestations(MigrateAttestationsArgs),
+}
+
+#[derive(clap::Args)]
+struct MigrateAttestationsArgs {
+    /// URL of the Redis attestation store to copy from.
+    #[arg(long, env = "ATTESTATION_CACHE_URL")]
+    from: String,
+
+    /// Either a `redis://` URL of a Redis attestation store or a `https://`
+    /// endpoint of the Cloudflare KV worker to copy into.
+    #[arg(long)]
+    to: Url,
+
+    /// Secret for signing requests to the Cloudflare KV worker.
+    #[arg(long, env = "CF_KV_SECRET", hide_env_values = true)]
+    cf_kv_secret: Option<String>,
+
+    /// Only scan the source store, without writing anything.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Maximum number of attestations processed per second, `0` means
+    /// unlimited.
+    #[arg(long, default_value_t = 100)]
+    rate_limit: u32,
+
+    /// Number of attestations requested from the source store at once.
+    #[arg(long, default_value_t = 100)]
+    batch_size: usize,
+
+    /// After copying, check that every attestation of the source store is
+    /// present in the destination one.
+    #[arg(long)]
+    verify: bool,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
-    let config = envy::from_env::<Configuration>().context("Failed to build config")?;
+    if let Some(command) = Cli::parse().command {
+        tracing_subscriber::fmt().init();
+        return match command {
+            Command::MigrateAttestations(args) => migrate_attestations(args).await,
+        };
+    }
+
+    let config = Configuration::from_env()?;
 
     let signals = shutdown_signals()?;
 
@@ -89,6 +391,17 @@ async fn main() -> Result<(), anyhow::Error> {
 
     let s3_client = s3_client(&config).await;
     let geoip_resolver = geoip_resolver(&config, &s3_client).await;
+    let readiness_provider = {
+        let geoip = geoip_resolver.clone();
+        move || Readiness {
+            // There's nothing to wait for without a GeoIP database configured.
+            geoip_ready: match &geoip {
+                Some(resolver) => resolver.is_ready(),
+                None => true,
+            },
+            geoip_build_epoch: geoip.as_ref().and_then(ReloadableResolver::build_epoch),
+        }
+    };
 
     // By default `axum_prometheus` exposes http latency as a Summary, which
     // provides quite limited querying functionaliy. `set_buckets_for_metrics`
@@ -102,54 +415,44 @@ async fn main() -> Result<(), anyhow::Error> {
         .install_recorder()
         .context("Failed to install Prometheus metrics recorder")?;
 
-    let attestation_store = {
-        let redis_attestation_store =
-            redis::new("attestation_store", config.attestation_cache_url.clone())
-                .context("Failed to initialize AttestationStore")?;
-        let cf_kv_attestation_store = CloudflareKv::new(
-            config
-                .cf_kv_endpoint
-                .parse()
-                .context("Failed to parse cf_kv_endpoint")?,
-            TokenManager::new(config.secret.as_bytes()),
-        );
-        migration::Store::new(redis_attestation_store, cf_kv_attestation_store)
-    };
-
-    let project_registry_cache = redis::new(
-        "project_registry_cache",
-        config.project_registry_cache_url.clone(),
-    )
-    .context("Failed to initialize project_registry::Cache")?;
+    let token_manager = config
+        .keyring
+        .keyring(config.secret.as_deref())
+        .map(TokenManager::new)
+        .context("Failed to initialize Keyring")?;
 
-    let project_registry = project_registry::cloud::new(
-        config.project_registry_url.clone(),
-        &config.project_registry_auth_token,
-    )
-    .context("Failed to initialize ProjectRegistry")?
-    .cached(project_registry_cache);
+    if let Some(path) = config.keyring.file.clone() {
+        let interval = Duration::from_secs(config.keyring.reload_interval_secs);
+        tokio::spawn(token_manager.clone().watch_keyring_file(path, interval));
+    }
 
-    let scam_guard_cache = redis::new("scam_guard_cache", config.scam_guard_cache_url.clone())
-        .context("Failed to initialize scam_guard::Cache")?;
+    let attestation_store = attestation_store(&config).await?;
 
-    let scam_guard = scam_guard::data_api::new(config.data_api_url, config.data_api_auth_token)
-        .cached(scam_guard_cache);
+    let (project_registry, project_sync) = project_registry(&config)?;
+    let scam_guard = scam_guard(&config)?;
+    let rate_limiter = rate_limiter(&config)?;
 
     let event_sink = if let Some(bucket) = config.data_lake_bucket {
-        Some(event_sink::s3::requests_dir(s3_client, bucket).await?)
+        let timeout = config.timeouts.analytics_export();
+        Some(event_sink::s3::requests_dir(s3_client, bucket, timeout).await?)
     } else {
         tracing::info!("data_lake_bucket is not specified, analytics are going to be disabled");
         None
     };
 
     let svc = bouncer::Service::new((attestation_store, project_registry, scam_guard))
+        .with_timeouts(config.timeouts)
         .observable(event_sink);
 
     let server_cfg = ServerConfig {
         port: config.port,
         metrics_port: config.prometheus_port,
-        secret: config.secret.as_bytes(),
-        blocked_countries: config.blocked_countries,
+        token_manager,
+        geoblock: config.geoblock,
+        security_headers: config.security_headers,
+        client_ip: config.client_ip,
+        project_sync,
+        rate_limiter,
     };
 
     bouncer::http_server::run(
@@ -157,6 +460,7 @@ async fn main() -> Result<(), anyhow::Error> {
         svc,
         move || prometheus.render(),
         health_provider,
+        readiness_provider,
         geoip_resolver,
         signals,
     )
@@ -165,6 +469,239 @@ async fn main() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+async fn attestation_store(
+    config: &Configuration,
+) -> Result<Box<dyn AttestationStore>, anyhow::Error> {
+    Ok(match config.attestation_store_backend {
+        AttestationStoreBackend::Replicated => Box::new(Replicated::new(
+            redis_attestation_store(config)?,
+            cf_kv_attestation_store(config)?,
+            config.attestation_store_replication,
+        )),
+        AttestationStoreBackend::Memory => Box::new(Memory::default()),
+        AttestationStoreBackend::Redis => Box::new(redis_attestation_store(config)?),
+        AttestationStoreBackend::CfKv => Box::new(cf_kv_attestation_store(config)?),
+        AttestationStoreBackend::Sql => {
+            let store = Sql::new(&config.attestation_store_sql)
+                .await
+                .context("Failed to initialize SQL AttestationStore")?;
+            tokio::spawn(
+                store
+                    .clone()
+                    .run_sweeper(config.attestation_store_sql.clone()),
+            );
+            Box::new(store)
+        }
+        AttestationStoreBackend::Disk => {
+            let store = Disk::new(&config.attestation_store_disk)
+                .context("Failed to initialize disk AttestationStore")?;
+            let interval = Duration::from_secs(config.attestation_store_disk.sweep_interval_secs);
+            tokio::spawn(store.clone().run_sweeper(interval));
+            Box::new(store)
+        }
+    })
+}
+
+fn redis_attestation_store(config: &Configuration) -> Result<redis::Adapter, anyhow::Error> {
+    let url = required(
+        config.attestation_cache_url.as_deref(),
+        "ATTESTATION_CACHE_URL",
+    )?;
+    redis::new("attestation_store", url).context("Failed to initialize AttestationStore")
+}
+
+fn cf_kv_attestation_store(config: &Configuration) -> Result<CloudflareKv, anyhow::Error> {
+    let endpoint = required(config.cf_kv_endpoint.as_deref(), "CF_KV_ENDPOINT")?;
+    let secret = required(config.cf_kv_secret.as_deref(), "CF_KV_SECRET")?;
+
+    Ok(CloudflareKv::new(
+        endpoint.parse().context("Failed to parse cf_kv_endpoint")?,
+        RequestSigner::new(secret.as_bytes()),
+        config.cf_kv_http.clone(),
+    ))
+}
+
+fn project_registry(
+    config: &Configuration,
+) -> Result<(Box<dyn ProjectRegistry>, Option<ProjectSyncWebhook>), anyhow::Error> {
+    let mut webhook = None;
+
+    let registry: Box<dyn ProjectRegistry> = match config.project_registry_backend {
+        ProjectRegistryBackend::Cloud => {
+            let registry = project_registry::cloud::new(
+                required(
+                    config.project_registry_url.as_deref(),
+                    "PROJECT_REGISTRY_URL",
+                )?,
+                required(
+                    config.project_registry_auth_token.as_deref(),
+                    "PROJECT_REGISTRY_AUTH_TOKEN",
+                )?,
+                config.project_registry_http.clone(),
+            )
+            .context("Failed to initialize ProjectRegistry")?;
+
+            match &config.project_registry_cache_url {
+                Some(url) => {
+                    let cache = redis::new("project_registry_cache", url.as_str())
+                        .context("Failed to initialize project_registry::Cache")?;
+                    Box::new(registry.cached(cache))
+                }
+                None => Box::new(registry),
+            }
+        }
+        ProjectRegistryBackend::File => {
+            let path = required(
+                config.project_registry_file.as_deref(),
+                "PROJECT_REGISTRY_FILE",
+            )?;
+            let registry = project_registry::file::File::new(path)
+                .context("Failed to initialize ProjectRegistry")?;
+
+            let interval = Duration::from_secs(config.project_registry_file_reload_interval_secs);
+            tokio::spawn(registry.clone().watch(path.to_owned(), interval));
+
+            Box::new(registry)
+        }
+        ProjectRegistryBackend::Synced => {
+            let sync = &config.project_registry_sync;
+            if sync.snapshot_url.is_none() && sync.webhook_secret.is_none() {
+                return Err(anyhow::anyhow!(
+                    "PROJECT_REGISTRY_SYNC_SNAPSHOT_URL or PROJECT_REGISTRY_SYNC_WEBHOOK_SECRET \
+                     is required by the selected backends"
+                ));
+            }
+
+            let registry = Synced::default();
+
+            if let Some(url) = sync.snapshot_url.clone() {
+                let interval = Duration::from_secs(sync.snapshot_interval_secs);
+                tokio::spawn(registry.clone().run_snapshot_sync(
+                    url,
+                    config.project_registry_auth_token.clone(),
+                    config.project_registry_http.clone(),
+                    interval,
+                ));
+            }
+
+            webhook = sync
+                .webhook_secret
+                .as_ref()
+                .map(|secret| ProjectSyncWebhook {
+                    registry: registry.clone(),
+                    signer: RequestSigner::new(secret.as_bytes()),
+                });
+
+            Box::new(registry)
+        }
+    };
+
+    Ok((registry, webhook))
+}
+
+fn scam_guard(config: &Configuration) -> Result<Box<dyn ScamGuard>, anyhow::Error> {
+    Ok(match config.scam_guard_backend {
+        ScamGuardBackend::DataApi => {
+            // The checks are cut off by the scam guard timeout, so it has to leave room for
+            // all the retries of the data API client.
+            anyhow::ensure!(
+                config.data_api_http.max_duration() <= config.timeouts.scam_guard(),
+                "DATA_API_HTTP_ timeouts and retries exceed TIMEOUTS_SCAM_GUARD_MS"
+            );
+
+            let scam_guard = scam_guard::data_api::new(
+                required(config.data_api_url.as_deref(), "DATA_API_URL")?.to_owned(),
+                required(config.data_api_auth_token.as_deref(), "DATA_API_AUTH_TOKEN")?.to_owned(),
+                config.data_api_http.clone(),
+            );
+
+            match &config.scam_guard_cache_url {
+                Some(url) => {
+                    let cache = redis::new("scam_guard_cache", url.as_str())
+                        .context("Failed to initialize scam_guard::Cache")?;
+                    Box::new(scam_guard.cached(cache))
+                }
+                None => Box::new(scam_guard),
+            }
+        }
+        ScamGuardBackend::Static => Box::new(scam_guard::Static::new(
+            config.scam_guard_static_domains.iter().cloned(),
+        )),
+        ScamGuardBackend::None => Box::new(scam_guard::Disabled),
+    })
+}
+
+fn rate_limiter(config: &Configuration) -> Result<Option<RateLimiter>, anyhow::Error> {
+    let limits = config.rate_limit.clone();
+
+    Ok(match config.rate_limit_backend {
+        RateLimitBackend::Memory => Some(RateLimiter::new(rate_limit::Memory::default(), limits)),
+        RateLimitBackend::Redis => {
+            let url = required(
+                config.rate_limit_redis_url.as_deref(),
+                "RATE_LIMIT_REDIS_URL",
+            )?;
+            let storage =
+                redis::new("rate_limit", url).context("Failed to initialize RateLimiter")?;
+            Some(RateLimiter::new(storage, limits))
+        }
+        RateLimitBackend::None => None,
+    })
+}
+
+/// Returns a config value required by the selected backends.
+fn required<'a, T: ?Sized>(value: Option<&'a T>, var: &str) -> Result<&'a T, anyhow::Error> {
+    value.with_context(|| format!("{var} is required by the selected backends"))
+}
+
+async fn migrate_attestations(args: MigrateAttestationsArgs) -> Result<(), anyhow::Error> {
+    let source = redis::new("attestation_store", args.from)
+        .context("Failed to initialize source AttestationStore")?;
+
+    let opts = backfill::Options {
+        dry_run: args.dry_run,
+        rate_limit: args.rate_limit,
+        batch_size: args.batch_size,
+    };
+
+    async fn run(
+        source: &impl bouncer::attestation_store::ScanAttestations,
+        destination: &impl bouncer::AttestationStore,
+        opts: backfill::Options,
+        verify: bool,
+    ) -> Result<(), anyhow::Error> {
+        let report = backfill::copy(source, destination, opts).await?;
+        info!(?report, "Attestations copied");
+
+        if verify {
+            let report = backfill::verify(source, destination, opts).await?;
+            info!(?report, "Attestations verified");
+        }
+
+        Ok(())
+    }
+
+    match args.to.scheme() {
+        "redis" | "rediss" => {
+            let destination = redis::new("attestation_store", args.to.as_str())
+                .context("Failed to initialize destination AttestationStore")?;
+            run(&source, &destination, opts, args.verify).await
+        }
+        "http" | "https" => {
+            let secret = args
+                .cf_kv_secret
+                .context("CF_KV_SECRET is required to copy into Cloudflare KV")?;
+            let destination = CloudflareKv::new(
+                args.to,
+                RequestSigner::new(secret.as_bytes()),
+                http::Config::default(),
+            );
+            run(&source, &destination, opts, args.verify).await
+        }
+        scheme => Err(anyhow::anyhow!("Unsupported destination scheme: {scheme}")),
+    }
+}
+
 fn shutdown_signals() -> Result<impl Future, anyhow::Error> {
     let mut term = signal(SignalKind::terminate()).context("Failed to install SIGTERM handler")?;
     let mut int = signal(SignalKind::interrupt()).context("Failed to install SIGINT handler")?;
@@ -206,6 +743,14 @@ fn default_prometheus_port() -> u16 {
     4000
 }
 
+fn default_project_registry_file_reload_interval_secs() -> u64 {
+    10
+}
+
+fn default_geoip_db_refresh_interval_secs() -> u64 {
+    60 * 60
+}
+
 fn default_log_level() -> tracing::Level {
     tracing::Level::INFO
 }
@@ -245,22 +790,44 @@ pub async fn s3_client(config: &Configuration) -> S3Client {
 async fn geoip_resolver(
     config: &Configuration,
     s3_client: &S3Client,
-) -> Option<Arc<MaxMindResolver>> {
-    match (&config.geoip_db_bucket, &config.geoip_db_key) {
-        (Some(bucket), Some(key)) => {
+) -> Option<ReloadableResolver> {
+    let source = match (
+        &config.geoip_db_path,
+        &config.geoip_db_bucket,
+        &config.geoip_db_key,
+    ) {
+        (Some(path), _, _) => {
+            info!(path = %path.display(), "initializing geoip database from a local file");
+            geoip::Source::File(path.clone())
+        }
+        (None, Some(bucket), Some(key)) => {
             info!(%bucket, %key, "initializing geoip database from aws s3");
-
-            MaxMindResolver::from_aws_s3(s3_client, bucket, key)
-                .await
-                .tap_err(|err| info!(?err, "failed to load geoip resolver"))
-                .ok()
-                .map(Arc::new)
+            geoip::Source::S3 {
+                client: s3_client.clone(),
+                bucket: bucket.clone(),
+                key: key.clone(),
+            }
         }
         _ => {
             info!("geoip lookup is disabled");
-            None
+            return None;
         }
-    }
+    };
+
+    // Geo features are served according to `GEOBLOCK_UNAVAILABLE` until the
+    // database gets loaded in the background.
+    let resolver = ReloadableResolver::default();
+    let _ = resolver.load(&source).await.tap_err(|err| {
+        warn!(
+            ?err,
+            "failed to load geoip database, retrying in the background"
+        )
+    });
+
+    let interval = Duration::from_secs(config.geoip_db_refresh_interval_secs);
+    tokio::spawn(resolver.clone().run_refresh(source, interval));
+
+    Some(resolver)
 }
 
 #[derive(Debug, Default, ParquetRecordWriter)]
@@ -281,15 +848,17 @@ struct RequestRecord {
 impl<'c, 'r> From<GetVerifyStatusHandled<'c, 'r, RequestInfo>> for RequestRecord {
     fn from(ev: GetVerifyStatusHandled<'c, 'r, RequestInfo>) -> Self {
         Self {
-            r#type: "get_verify_status",
+            r#type: match ev.cmd.context.kind {
+                RequestKind::Enclave => "get_verify_status",
+                RequestKind::TokenRefresh => "refresh_csrf_token",
+                RequestKind::Diagnostics => "get_verify_diagnostics",
+                RequestKind::Attestation => "check_attestation_origin",
+            },
             success: ev.result.is_ok(),
             project_id: Some(ev.cmd.inner.project_id.as_ref().to_string()),
-            verify_status: ev.result.as_ref().ok().map(|status| match status {
-                VerifyStatus::Disabled => "disabled",
-                VerifyStatus::Enabled { .. } => "enabled",
-            }),
+            verify_status: ev.result.as_ref().ok().map(|s| s.status.as_str()),
             user_agent: ev.cmd.context.user_agent,
-            country: ev.cmd.context.country,
+            country: ev.cmd.context.location.map(|l| l.country),
             ..Default::default()
         }
     }
@@ -303,7 +872,7 @@ impl<'c, 'r> From<SetAttestationHandled<'c, 'r, RequestInfo>> for RequestRecord
             attestation_id: Some(ev.cmd.inner.id.to_string()),
             origin: Some(ev.cmd.inner.origin.to_string()),
             user_agent: ev.cmd.context.user_agent,
-            country: ev.cmd.context.country,
+            country: ev.cmd.context.location.map(|l| l.country),
             ..Default::default()
         }
     }
@@ -324,7 +893,7 @@ impl<'c, 'r> From<GetAttestationHandled<'c, 'r, RequestInfo>> for RequestRecord
                 IsScam::Unknown => None,
             }),
             user_agent: ev.cmd.context.user_agent,
-            country: ev.cmd.context.country,
+            country: ev.cmd.context.location.map(|l| l.country),
             ..Default::default()
         }
     }
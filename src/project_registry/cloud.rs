@@ -1,24 +1,43 @@
 use {
     super::{ProjectData, ProjectRegistry, Result},
-    crate::{Domain, ProjectId},
+    crate::{
+        util::{
+            http::{self, Idempotency},
+            time::unix_timestamp,
+        },
+        Domain,
+        ProjectId,
+    },
     async_trait::async_trait,
     cerberus::registry::{RegistryClient, RegistryHttpClient},
     metrics::counter,
     tap::{Tap, TapFallible},
 };
 
-pub fn new(base_url: impl Into<String>, auth_token: &str) -> Result<impl ProjectRegistry> {
-    Ok(RegistryHttpClient::with_config(
-        base_url,
-        auth_token,
-        Default::default(),
-    )?)
+struct Adapter {
+    client: RegistryHttpClient,
+    http: http::Client,
+}
+
+pub fn new(
+    base_url: impl Into<String>,
+    auth_token: &str,
+    http_config: http::Config,
+) -> Result<impl ProjectRegistry> {
+    Ok(Adapter {
+        client: RegistryHttpClient::with_config(base_url, auth_token, Default::default())?,
+        http: http::Client::new("project_registry", http_config),
+    })
 }
 
 #[async_trait]
-impl ProjectRegistry for RegistryHttpClient {
+impl ProjectRegistry for Adapter {
     async fn project_data(&self, id: &ProjectId) -> Result<Option<ProjectData>> {
-        let data = RegistryClient::project_data(self, id.as_ref())
+        let data = self
+            .http
+            .call(Idempotency::Idempotent, || {
+                RegistryClient::project_data(&self.client, id.as_ref())
+            })
             .await
             .tap(|_| counter!("project_registry_requests", 1))
             .tap_err(|_| counter!("project_registry_errors", 1))?;
@@ -29,6 +48,9 @@ impl ProjectRegistry for RegistryHttpClient {
         Ok(Some(ProjectData {
             is_verify_enabled: data.is_verify_enabled,
             verified_domains: data.verified_domains.into_iter().map(Domain).collect(),
+            // The registry doesn't expose per-project Verify settings yet.
+            verify_config: Default::default(),
+            fetched_at: Some(unix_timestamp()),
         }))
     }
 }
@@ -0,0 +1,195 @@
+use {
+    super::{ProjectData, ProjectRegistry, Result},
+    crate::{util::watch::watch_file, ProjectId},
+    anyhow::{anyhow, Context as _},
+    async_trait::async_trait,
+    metrics::counter,
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+        sync::{Arc, RwLock},
+        time::Duration,
+    },
+    tap::TapFallible as _,
+    tracing::{info, warn},
+};
+
+type Projects = HashMap<ProjectId, ProjectData>;
+
+/// Format of a projects file, determined by its extension.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Format {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    fn from_path(path: &Path) -> Result<Self> {
+        Ok(match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Self::Json,
+            Some("yaml" | "yml") => Self::Yaml,
+            Some("toml") => Self::Toml,
+            _ => {
+                return Err(anyhow!(
+                    "Unsupported projects file extension, expected .json, .yaml, .yml or .toml"
+                ))
+            }
+        })
+    }
+
+    fn parse(self, s: &str) -> Result<HashMap<String, ProjectData>> {
+        Ok(match self {
+            Self::Json => serde_json::from_str(s)?,
+            Self::Yaml => serde_norway::from_str(s)?,
+            Self::Toml => toml::from_str(s)?,
+        })
+    }
+}
+
+/// [`ProjectRegistry`] serving the projects of a local JSON, YAML or TOML
+/// file, keyed by their IDs:
+///
+/// ```yaml
+/// <project_id>:
+///   is_verify_enabled: true
+///   verified_domains: [my-dapp.io]
+/// ```
+#[derive(Clone)]
+pub struct File {
+    projects: Arc<RwLock<Arc<Projects>>>,
+}
+
+impl File {
+    /// Loads the projects from the file at `path`, failing if any of them is
+    /// invalid.
+    pub fn new(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self {
+            projects: Arc::new(RwLock::new(Arc::new(load(path.as_ref())?))),
+        })
+    }
+
+    fn projects(&self) -> Arc<Projects> {
+        self.projects.read().unwrap().clone()
+    }
+
+    /// Periodically reloads the projects from the file at `path` whenever its
+    /// modification time changes, keeping the current ones if the file is
+    /// invalid.
+    pub async fn watch(self, path: PathBuf, interval: Duration) {
+        watch_file(path, interval, |path| {
+            let projects = load(path).tap_err(|e| {
+                warn!("Failed to reload projects file: {e:?}");
+                counter!("project_registry_file_reload_errors", 1);
+            })?;
+
+            info!(projects = projects.len(), "Projects file reloaded");
+            *self.projects.write().unwrap() = Arc::new(projects);
+            Ok(())
+        })
+        .await
+    }
+}
+
+fn load(path: &Path) -> Result<Projects> {
+    let format = Format::from_path(path)?;
+    let s = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let projects = format
+        .parse(&s)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    validate(projects).map_err(|errors| {
+        anyhow!(
+            "Invalid projects in {}:\n{}",
+            path.display(),
+            errors.join("\n")
+        )
+    })
+}
+
+/// Checks the project IDs, domains and schemes, returning every found error.
+pub(super) fn validate(
+    projects: HashMap<String, ProjectData>,
+) -> std::result::Result<Projects, Vec<String>> {
+    let mut errors = vec![];
+    let mut valid = Projects::with_capacity(projects.len());
+
+    for (id, data) in projects {
+        let project_id = match id.parse::<ProjectId>() {
+            Ok(project_id) => project_id,
+            Err(e) => {
+                errors.push(format!("{id}: {e}"));
+                continue;
+            }
+        };
+
+        for domain in data.verified_domains.iter().filter(|d| !d.is_valid()) {
+            let domain: &String = domain.as_ref();
+            errors.push(format!(
+                "{id}: Domain({domain}) should be a lowercase hostname without a scheme, port or \
+                 path"
+            ));
+        }
+
+        for scheme in data.verify_config.invalid_schemes() {
+            errors.push(format!(
+                "{id}: Scheme({scheme}) should match [a-z][a-z0-9+.-]*"
+            ));
+        }
+
+        valid.insert(project_id, data);
+    }
+
+    if errors.is_empty() {
+        Ok(valid)
+    } else {
+        errors.sort();
+        Err(errors)
+    }
+}
+
+#[async_trait]
+impl ProjectRegistry for File {
+    async fn project_data(&self, id: &ProjectId) -> Result<Option<ProjectData>> {
+        Ok(self.projects().get(id).cloned())
+    }
+}
+
+#[test]
+fn test_load() {
+    const ID: &str = "0123456789abcdef0123456789abcdef";
+
+    let yaml = format!("{ID}:\n  is_verify_enabled: true\n  verified_domains: [my-dapp.io]\n");
+    let toml = format!("[{ID}]\nis_verify_enabled = true\nverified_domains = [\"my-dapp.io\"]\n");
+    let json = format!(
+        r#"{{ "{ID}": {{ "is_verify_enabled": true, "verified_domains": ["my-dapp.io"] }} }}"#
+    );
+
+    for (format, s) in [
+        (Format::Yaml, yaml),
+        (Format::Toml, toml),
+        (Format::Json, json),
+    ] {
+        let projects = validate(format.parse(&s).unwrap()).unwrap();
+        let data = &projects[&ID.parse().unwrap()];
+        assert!(data.is_verify_enabled, "{format:?}");
+        assert_eq!(data.verified_domains.len(), 1, "{format:?}");
+    }
+
+    let json = format!(
+        r#"{{
+            "invalid": {{ "is_verify_enabled": true, "verified_domains": [] }},
+            "{ID}": {{
+                "is_verify_enabled": true,
+                "verified_domains": ["https://a.io", "a.io:80", "A.io", "localhost"],
+                "verify_config": {{ "allowed_schemes": ["https", "https: *; script-src *"] }}
+            }}
+        }}"#
+    );
+    let errors = validate(Format::Json.parse(&json).unwrap()).unwrap_err();
+    assert_eq!(errors.len(), 5, "{errors:?}");
+
+    assert!(Format::from_path(Path::new("projects.yml")).is_ok());
+    assert!(Format::from_path(Path::new("projects.txt")).is_err());
+}
@@ -1,4 +1,6 @@
 pub mod cloud;
+pub mod file;
+pub mod sync;
 
 pub use cache::{Cache, Cached, CachedExt};
 use {
@@ -16,6 +18,13 @@ pub trait ProjectRegistry: Send + Sync + 'static {
 pub type Error = anyhow::Error;
 pub type Result<T> = std::result::Result<T, Error>;
 
+#[async_trait]
+impl<R: ProjectRegistry + ?Sized> ProjectRegistry for Box<R> {
+    async fn project_data(&self, id: &ProjectId) -> Result<Option<ProjectData>> {
+        (**self).project_data(id).await
+    }
+}
+
 #[async_trait]
 impl<R, C> ProjectRegistry for Cached<R, C>
 where
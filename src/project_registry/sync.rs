@@ -0,0 +1,244 @@
+use {
+    super::{file, ProjectData, ProjectRegistry, Result},
+    crate::{
+        util::{
+            http::{self, Idempotency},
+            time::unix_timestamp,
+        },
+        ProjectId,
+    },
+    anyhow::{anyhow, Context as _},
+    async_trait::async_trait,
+    metrics::{counter, gauge},
+    serde::Deserialize,
+    std::{
+        collections::HashMap,
+        sync::{Arc, RwLock},
+        time::Duration,
+    },
+    tracing::{info, warn},
+};
+
+type Projects = HashMap<ProjectId, ProjectData>;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// URL of the full snapshot of the registry, in the format of the
+    /// [`file`] registry. If not set, snapshots are only pushed via the
+    /// webhook.
+    pub snapshot_url: Option<String>,
+
+    /// How often the full snapshot is fetched.
+    pub snapshot_interval_secs: u64,
+
+    /// Secret the webhook requests are signed with, the webhook is disabled
+    /// if not set.
+    pub webhook_secret: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            snapshot_url: None,
+            snapshot_interval_secs: 300,
+            webhook_secret: None,
+        }
+    }
+}
+
+/// Update of a [`Synced`] registry, pushed via the webhook.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Update {
+    /// Replaces every project.
+    Snapshot {
+        projects: HashMap<String, ProjectData>,
+    },
+
+    /// Upserts and deletes individual projects.
+    Delta {
+        #[serde(default)]
+        upserts: HashMap<String, ProjectData>,
+        #[serde(default)]
+        deletes: Vec<ProjectId>,
+    },
+}
+
+/// [`ProjectRegistry`] serving every project from memory, kept in sync by
+/// periodic full snapshots and pushed [`Update`]s.
+///
+/// A registry outage only delays the updates, while verification keeps being
+/// served from the last synced state. Until the first snapshot is loaded
+/// every lookup fails, as missing projects can't be told apart from unknown
+/// ones.
+#[derive(Clone, Default)]
+pub struct Synced {
+    projects: Arc<RwLock<Option<Arc<Projects>>>>,
+}
+
+impl Synced {
+    fn projects(&self) -> Option<Arc<Projects>> {
+        self.projects.read().unwrap().clone()
+    }
+
+    fn replace(&self, projects: Projects) {
+        gauge!("project_registry_synced_projects", projects.len() as f64);
+        *self.projects.write().unwrap() = Some(Arc::new(projects));
+    }
+
+    /// Applies an [`Update`], failing if it contains invalid projects.
+    ///
+    /// A delta is rejected until the first snapshot is loaded.
+    pub fn apply(&self, update: Update) -> Result<()> {
+        match update {
+            Update::Snapshot { projects } => {
+                self.replace(validate(projects)?);
+                counter!("project_registry_sync_updates", 1, "type" => "snapshot");
+            }
+            Update::Delta { upserts, deletes } => {
+                let upserts = validate(upserts)?;
+
+                let mut guard = self.projects.write().unwrap();
+                let projects = guard
+                    .as_mut()
+                    .ok_or_else(|| anyhow!("No snapshot to apply the delta to"))?;
+
+                let projects = Arc::make_mut(projects);
+                for id in &deletes {
+                    projects.remove(id);
+                }
+                projects.extend(upserts);
+
+                gauge!("project_registry_synced_projects", projects.len() as f64);
+                counter!("project_registry_sync_updates", 1, "type" => "delta");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Periodically replaces every project with the snapshot fetched from
+    /// `url`, keeping the current ones if the fetch fails.
+    ///
+    /// A snapshot fetched before a concurrently pushed delta may revert it
+    /// until the next snapshot.
+    pub async fn run_snapshot_sync(
+        self,
+        url: String,
+        auth_token: Option<String>,
+        http_config: http::Config,
+        interval: Duration,
+    ) {
+        let client = http::Client::new("project_registry_snapshot", http_config);
+        let mut interval = tokio::time::interval(interval);
+
+        loop {
+            interval.tick().await;
+
+            match fetch_snapshot(&client, &url, auth_token.as_deref()).await {
+                Ok(projects) => {
+                    info!(
+                        projects = projects.len(),
+                        "Project registry snapshot synced"
+                    );
+                    self.replace(projects);
+                    counter!("project_registry_snapshot_syncs", 1, "outcome" => "ok");
+                }
+                Err(e) => {
+                    warn!("Failed to sync project registry snapshot: {e:?}");
+                    counter!("project_registry_snapshot_syncs", 1, "outcome" => "error");
+                }
+            }
+        }
+    }
+}
+
+async fn fetch_snapshot(
+    client: &http::Client,
+    url: &str,
+    auth_token: Option<&str>,
+) -> Result<Projects> {
+    let projects = client
+        .send(Idempotency::Idempotent, |client| match auth_token {
+            Some(token) => client.get(url).bearer_auth(token),
+            None => client.get(url),
+        })
+        .await
+        .context("Snapshot request failed")?
+        .error_for_status()?
+        .json()
+        .await
+        .context("Failed to deserialize snapshot")?;
+
+    validate(projects)
+}
+
+/// Validates the synced projects, marking them as fetched now.
+fn validate(projects: HashMap<String, ProjectData>) -> Result<Projects> {
+    let mut projects = file::validate(projects)
+        .map_err(|errors| anyhow!("Invalid projects:\n{}", errors.join("\n")))?;
+
+    let now = unix_timestamp();
+    for data in projects.values_mut() {
+        data.fetched_at = Some(now);
+    }
+
+    Ok(projects)
+}
+
+#[async_trait]
+impl ProjectRegistry for Synced {
+    async fn project_data(&self, id: &ProjectId) -> Result<Option<ProjectData>> {
+        let projects = self
+            .projects()
+            .ok_or_else(|| anyhow!("Project registry snapshot isn't loaded yet"))?;
+
+        Ok(projects.get(id).cloned())
+    }
+}
+
+#[tokio::test]
+async fn test_synced() {
+    const A: &str = "0123456789abcdef0123456789abcdef";
+    const B: &str = "fedcba9876543210fedcba9876543210";
+
+    async fn has(registry: &Synced, id: &str) -> Result<bool> {
+        let data = registry.project_data(&id.parse().unwrap()).await?;
+        Ok(data.is_some())
+    }
+
+    let update = |json: &str| serde_json::from_str::<Update>(json).unwrap();
+    let registry = Synced::default();
+
+    assert!(has(&registry, A).await.is_err());
+    assert!(registry
+        .apply(update(&format!(
+            r#"{{ "type": "delta", "deletes": ["{A}"] }}"#
+        )))
+        .is_err());
+
+    registry
+        .apply(update(&format!(
+            r#"{{ "type": "snapshot", "projects": {{ "{A}": {{ "is_verify_enabled": true, "verified_domains": [] }} }} }}"#
+        )))
+        .unwrap();
+    assert!(has(&registry, A).await.unwrap());
+    assert!(!has(&registry, B).await.unwrap());
+
+    registry
+        .apply(update(&format!(
+            r#"{{ "type": "delta", "upserts": {{ "{B}": {{ "is_verify_enabled": true, "verified_domains": [] }} }}, "deletes": ["{A}"] }}"#
+        )))
+        .unwrap();
+    assert!(!has(&registry, A).await.unwrap());
+    assert!(has(&registry, B).await.unwrap());
+
+    // Invalid updates are rejected as a whole.
+    assert!(registry
+        .apply(update(&format!(
+            r#"{{ "type": "delta", "upserts": {{ "{A}": {{ "is_verify_enabled": true, "verified_domains": ["https://a.io"] }} }} }}"#
+        )))
+        .is_err());
+    assert!(!has(&registry, A).await.unwrap());
+}
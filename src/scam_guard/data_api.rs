@@ -1,6 +1,9 @@
 use {
     super::{Result, ScamGuard},
-    crate::IsScam,
+    crate::{
+        util::http::{self, Idempotency},
+        IsScam,
+    },
     anyhow::Context as _,
     async_trait::async_trait,
     reqwest::Url,
@@ -13,15 +16,15 @@ const API_KEY_HEADER: &str = "x-api-key";
 
 #[derive(Clone, Debug)]
 struct Adapter {
-    client: reqwest::Client,
+    client: http::Client,
 
     url: Arc<str>,
     key: Arc<str>,
 }
 
-pub fn new(url: String, key: String) -> impl ScamGuard {
+pub fn new(url: String, key: String, http_config: http::Config) -> impl ScamGuard {
     Adapter {
-        client: reqwest::Client::new(),
+        client: http::Client::new("data_api", http_config),
         url: url.into(),
         key: key.into(),
     }
@@ -42,12 +45,13 @@ impl ScamGuard for Adapter {
         };
 
         let url = format!("{}/domain?domain={host}", self.url.as_ref());
-        let req = self
+        let resp = self
             .client
-            .get(url)
-            .header(API_KEY_HEADER, self.key.as_ref());
-
-        let resp = req.send().await.context("data API request failed")?;
+            .send(Idempotency::Idempotent, |client| {
+                client.get(&url).header(API_KEY_HEADER, self.key.as_ref())
+            })
+            .await
+            .context("data API request failed")?;
         if resp.status() == 404 {
             return Ok(IsScam::Unknown);
         }
@@ -7,6 +7,8 @@ use {
     },
     async_trait::async_trait,
     metrics::counter,
+    reqwest::Url,
+    std::collections::HashSet,
     tap::TapFallible as _,
     tracing::{debug, error, instrument},
 };
@@ -20,6 +22,51 @@ pub trait ScamGuard: Send + Sync + 'static {
 pub type Error = anyhow::Error;
 pub type Result<T> = std::result::Result<T, Error>;
 
+#[async_trait]
+impl<S: ScamGuard + ?Sized> ScamGuard for Box<S> {
+    async fn is_scam(&self, domain: &str) -> Result<IsScam> {
+        (**self).is_scam(domain).await
+    }
+}
+
+/// [`ScamGuard`] checking domains against a fixed list of scam domains.
+pub struct Static {
+    scam_domains: HashSet<String>,
+}
+
+impl Static {
+    pub fn new(scam_domains: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            scam_domains: scam_domains.into_iter().collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl ScamGuard for Static {
+    async fn is_scam(&self, origin: &str) -> Result<IsScam> {
+        let url = Url::parse(origin).ok();
+        let Some(host) = url.as_ref().and_then(|url| url.host_str()) else {
+            return Ok(IsScam::Unknown);
+        };
+
+        Ok(match self.scam_domains.contains(host) {
+            true => IsScam::Yes,
+            false => IsScam::No,
+        })
+    }
+}
+
+/// [`ScamGuard`] not checking anything, every domain is [`IsScam::Unknown`].
+pub struct Disabled;
+
+#[async_trait]
+impl ScamGuard for Disabled {
+    async fn is_scam(&self, _origin: &str) -> Result<IsScam> {
+        Ok(IsScam::Unknown)
+    }
+}
+
 #[async_trait]
 impl<S, C> ScamGuard for Cached<S, C>
 where
@@ -62,3 +109,56 @@ where
         Ok(data)
     }
 }
+
+#[tokio::test]
+async fn test_cached_over_failing() {
+    use crate::cache::CachedExt as _;
+
+    struct Failing;
+
+    #[async_trait]
+    impl ScamGuard for Failing {
+        async fn is_scam(&self, _: &str) -> Result<IsScam> {
+            Err(anyhow::anyhow!("down"))
+        }
+    }
+
+    // Cached verdicts are served while the upstream is down.
+    #[derive(Clone)]
+    struct Hit(IsScam);
+
+    #[async_trait]
+    impl<'a> Cache<&'a str, IsScam> for Hit {
+        async fn set(&self, _: &&'a str, _: &IsScam) -> anyhow::Result<()>
+        where
+            &'a str: 'async_trait,
+            IsScam: 'async_trait,
+        {
+            Ok(())
+        }
+
+        async fn get(&self, _: &&'a str) -> anyhow::Result<cache::Output<IsScam>>
+        where
+            &'a str: 'async_trait,
+        {
+            Ok(cache::Output::Hit(self.0))
+        }
+    }
+
+    assert!(Failing.is_scam("a.com").await.is_err());
+
+    let guard = Failing.cached(Hit(IsScam::Yes));
+    assert_eq!(guard.is_scam("a.com").await.unwrap(), IsScam::Yes);
+}
+
+#[tokio::test]
+async fn test_static() {
+    let guard = Static::new(["evil.com".to_owned()]);
+
+    assert_eq!(
+        guard.is_scam("https://evil.com").await.unwrap(),
+        IsScam::Yes
+    );
+    assert_eq!(guard.is_scam("https://good.com").await.unwrap(), IsScam::No);
+    assert_eq!(guard.is_scam("not a url").await.unwrap(), IsScam::Unknown);
+}
@@ -0,0 +1,142 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// State of a [`CircuitBreaker`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum State {
+    /// Calls are allowed.
+    Closed { failures: u32 },
+
+    /// Calls are rejected until the deadline.
+    Open { until: Instant },
+
+    /// A single probe call is allowed, which either closes or re-opens the
+    /// breaker.
+    HalfOpen { since: Instant },
+}
+
+impl State {
+    /// Numeric representation of the state to be exposed as a metric.
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            Self::Closed { .. } => 0.0,
+            Self::HalfOpen { .. } => 1.0,
+            Self::Open { .. } => 2.0,
+        }
+    }
+}
+
+/// Circuit breaker opening after a number of consecutive failures.
+///
+/// After staying open for the cooldown period it half-opens, letting a
+/// single probe call through.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    state: Mutex<State>,
+}
+
+impl CircuitBreaker {
+    /// Creates a new [`CircuitBreaker`].
+    ///
+    /// A `failure_threshold` of `0` disables the breaker.
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold,
+            cooldown,
+            state: Mutex::new(State::Closed { failures: 0 }),
+        }
+    }
+
+    pub fn state(&self) -> State {
+        *self.state.lock().unwrap()
+    }
+
+    /// Checks whether a call is allowed to be made.
+    ///
+    /// Every allowed call should be followed by [`CircuitBreaker::record`].
+    pub fn try_acquire(&self) -> bool {
+        self.try_acquire_at(Instant::now())
+    }
+
+    fn try_acquire_at(&self, now: Instant) -> bool {
+        if self.failure_threshold == 0 {
+            return true;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        match *state {
+            State::Closed { .. } => true,
+            State::Open { until } if now < until => false,
+            // A probe may never be recorded (e.g. if its future gets dropped), so
+            // allow another one once the cooldown is over.
+            State::HalfOpen { since } if now < since + self.cooldown => false,
+            State::Open { .. } | State::HalfOpen { .. } => {
+                *state = State::HalfOpen { since: now };
+                true
+            }
+        }
+    }
+
+    /// Records the outcome of a call.
+    pub fn record(&self, success: bool) {
+        self.record_at(success, Instant::now())
+    }
+
+    fn record_at(&self, success: bool, now: Instant) {
+        if self.failure_threshold == 0 {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        *state = match (*state, success) {
+            (_, true) => State::Closed { failures: 0 },
+            (State::Closed { failures }, false) if failures + 1 < self.failure_threshold => {
+                State::Closed {
+                    failures: failures + 1,
+                }
+            }
+            (_, false) => State::Open {
+                until: now + self.cooldown,
+            },
+        };
+    }
+}
+
+#[test]
+fn test_circuit_breaker() {
+    let cooldown = Duration::from_secs(10);
+    let breaker = CircuitBreaker::new(2, cooldown);
+    let now = Instant::now();
+
+    assert!(breaker.try_acquire_at(now));
+    breaker.record_at(false, now);
+    assert!(breaker.try_acquire_at(now));
+    breaker.record_at(false, now);
+    assert_eq!(breaker.state(), State::Open {
+        until: now + cooldown
+    });
+    assert!(!breaker.try_acquire_at(now));
+
+    // Half-open: a single probe is allowed.
+    let later = now + cooldown;
+    assert!(breaker.try_acquire_at(later));
+    assert!(!breaker.try_acquire_at(later));
+
+    // Failed probe re-opens the breaker.
+    breaker.record_at(false, later);
+    assert!(!breaker.try_acquire_at(later));
+
+    // Successful probe closes the breaker.
+    let later = later + cooldown;
+    assert!(breaker.try_acquire_at(later));
+    breaker.record_at(true, later);
+    assert_eq!(breaker.state(), State::Closed { failures: 0 });
+
+    let disabled = CircuitBreaker::new(0, cooldown);
+    disabled.record_at(false, now);
+    assert!(disabled.try_acquire_at(now));
+}
@@ -0,0 +1,200 @@
+use {
+    anyhow::Context as _,
+    aws_sdk_s3::Client as S3Client,
+    bytes::Bytes,
+    metrics::{counter, gauge},
+    std::{
+        net::IpAddr,
+        path::PathBuf,
+        sync::{Arc, RwLock},
+        time::{Duration, SystemTime},
+    },
+    tracing::{info, warn},
+    wc::geoip::{self, MaxMindResolver, MaxMindResolverError},
+};
+
+pub type Error = anyhow::Error;
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// How often loading is retried while no database is loaded.
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Where the GeoIP database is loaded from.
+#[derive(Clone, Debug)]
+pub enum Source {
+    File(PathBuf),
+    S3 {
+        client: S3Client,
+        bucket: String,
+        key: String,
+    },
+}
+
+/// Version of a database in its [`Source`], used to skip reloading an
+/// unchanged database.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Version {
+    /// Modification time of a file.
+    Modified(SystemTime),
+
+    /// ETag of an S3 object.
+    ETag(String),
+}
+
+impl Source {
+    /// Fetches the database along with its version, `None` if it's still the
+    /// `current` version.
+    async fn fetch(&self, current: Option<&Version>) -> Result<Option<(Bytes, Option<Version>)>> {
+        match self {
+            Self::File(path) => {
+                let version = std::fs::metadata(path)
+                    .and_then(|m| m.modified())
+                    .ok()
+                    .map(Version::Modified);
+                if version.is_some() && version.as_ref() == current {
+                    return Ok(None);
+                }
+
+                let buffer = tokio::fs::read(path)
+                    .await
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+
+                Ok(Some((buffer.into(), version)))
+            }
+            Self::S3 {
+                client,
+                bucket,
+                key,
+            } => {
+                let etag = match current {
+                    Some(Version::ETag(etag)) => Some(etag.clone()),
+                    _ => None,
+                };
+
+                let output = match client
+                    .get_object()
+                    .bucket(bucket)
+                    .key(key)
+                    .set_if_none_match(etag)
+                    .send()
+                    .await
+                {
+                    Ok(output) => output,
+                    // S3 responds with `304 Not Modified` if the ETag still matches.
+                    Err(e) if e.raw_response().is_some_and(|r| r.status().as_u16() == 304) => {
+                        return Ok(None)
+                    }
+                    Err(e) => return Err(e).context("Failed to get the GeoIP database from S3"),
+                };
+
+                let version = output.e_tag().map(|etag| Version::ETag(etag.to_owned()));
+                let buffer = output
+                    .body
+                    .collect()
+                    .await
+                    .context("Failed to download the GeoIP database from S3")?
+                    .into_bytes();
+
+                Ok(Some((buffer, version)))
+            }
+        }
+    }
+}
+
+struct Database {
+    resolver: MaxMindResolver,
+    build_epoch: Option<u64>,
+    version: Option<Version>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LookupError {
+    #[error("GeoIP database isn't loaded")]
+    NotLoaded,
+
+    #[error(transparent)]
+    MaxMind(#[from] MaxMindResolverError),
+}
+
+/// [`geoip::Resolver`] which database is periodically reloaded and swapped
+/// atomically, so lookups are never blocked by a reload.
+///
+/// Lookups fail until the first database is loaded.
+#[derive(Clone, Default)]
+pub struct ReloadableResolver {
+    db: Arc<RwLock<Option<Arc<Database>>>>,
+}
+
+impl ReloadableResolver {
+    fn db(&self) -> Option<Arc<Database>> {
+        self.db.read().unwrap().clone()
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.db().is_some()
+    }
+
+    /// Unix timestamp of when the loaded database was built.
+    pub fn build_epoch(&self) -> Option<u64> {
+        self.db()?.build_epoch
+    }
+
+    /// Loads the database from the `source`, replacing the current one unless
+    /// it's unchanged. Returns whether the database was replaced.
+    pub async fn load(&self, source: &Source) -> Result<bool> {
+        let current = self.db().and_then(|db| db.version.clone());
+        let Some((buffer, version)) = source.fetch(current.as_ref()).await? else {
+            return Ok(false);
+        };
+
+        let build_epoch = maxminddb::Reader::from_source(&buffer[..])
+            .map(|reader| reader.metadata.build_epoch)
+            .ok();
+        let resolver =
+            MaxMindResolver::from_buffer(buffer).context("Failed to parse the GeoIP database")?;
+
+        *self.db.write().unwrap() = Some(Arc::new(Database {
+            resolver,
+            build_epoch,
+            version,
+        }));
+
+        if let Some(epoch) = build_epoch {
+            gauge!("geoip_db_build_epoch", epoch as f64);
+        }
+        info!(?build_epoch, "GeoIP database loaded");
+
+        Ok(true)
+    }
+
+    /// Periodically reloads the database from the `source`, keeping the
+    /// current one if the reload fails.
+    pub async fn run_refresh(self, source: Source, interval: Duration) {
+        loop {
+            let delay = if self.is_ready() {
+                interval
+            } else {
+                interval.min(RETRY_INTERVAL)
+            };
+            tokio::time::sleep(delay).await;
+
+            match self.load(&source).await {
+                Ok(false) => {}
+                Ok(true) => counter!("geoip_db_reloads", 1, "outcome" => "ok"),
+                Err(e) => {
+                    warn!("Failed to reload the GeoIP database: {e:?}");
+                    counter!("geoip_db_reloads", 1, "outcome" => "error");
+                }
+            }
+        }
+    }
+}
+
+impl geoip::Resolver for ReloadableResolver {
+    type Error = LookupError;
+
+    fn lookup_geo_data(&self, addr: IpAddr) -> std::result::Result<geoip::Data, Self::Error> {
+        let db = self.db().ok_or(LookupError::NotLoaded)?;
+        Ok(db.resolver.lookup_geo_data(addr)?)
+    }
+}
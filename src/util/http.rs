@@ -0,0 +1,294 @@
+use {
+    super::circuit_breaker::CircuitBreaker,
+    futures::TryFutureExt as _,
+    metrics::{counter, gauge, histogram},
+    rand::Rng as _,
+    reqwest::{RequestBuilder, Response, StatusCode},
+    serde::Deserialize,
+    std::{
+        future::Future,
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+    tracing::warn,
+};
+
+/// Configuration of an outbound HTTP [`Client`].
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Timeout of a single attempt.
+    pub timeout_ms: u64,
+
+    /// Number of retries of idempotent calls.
+    pub max_retries: u32,
+
+    /// Base delay of the exponential backoff between retries. The actual delay
+    /// is randomly picked between zero and the backoff.
+    pub retry_base_delay_ms: u64,
+
+    /// Number of consecutive failures opening the circuit breaker, `0`
+    /// disables the breaker.
+    pub breaker_failure_threshold: u32,
+
+    /// For how long the circuit breaker stays open.
+    pub breaker_cooldown_ms: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 1000,
+            max_retries: 2,
+            retry_base_delay_ms: 50,
+            breaker_failure_threshold: 5,
+            breaker_cooldown_ms: 10_000,
+        }
+    }
+}
+
+impl Config {
+    /// Longest possible duration of an idempotent call, with every attempt
+    /// timing out and the longest backoff between them.
+    pub fn max_duration(&self) -> Duration {
+        let backoff: u64 = (0..self.max_retries)
+            .map(|n| self.retry_base_delay_ms.saturating_mul(1 << n.min(16)))
+            .fold(0, u64::saturating_add);
+
+        Duration::from_millis(
+            self.timeout_ms
+                .saturating_mul(u64::from(self.max_retries) + 1)
+                .saturating_add(backoff),
+        )
+    }
+}
+
+/// Error of an outbound call made via [`Client`].
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Circuit breaker of {0} is open")]
+    CircuitOpen(&'static str),
+
+    #[error("Request to {0} timed out")]
+    Timeout(&'static str),
+
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+/// Whether a call may be safely retried.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Idempotency {
+    Idempotent,
+    NonIdempotent,
+}
+
+/// Client of an upstream service, applying timeouts, retries and a circuit
+/// breaker to every call and emitting uniform metrics labeled with the
+/// upstream name.
+#[derive(Clone, Debug)]
+pub struct Client {
+    upstream: &'static str,
+    config: Config,
+    http: reqwest::Client,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl Client {
+    pub fn new(upstream: &'static str, config: Config) -> Self {
+        Self {
+            upstream,
+            http: reqwest::Client::new(),
+            breaker: Arc::new(CircuitBreaker::new(
+                config.breaker_failure_threshold,
+                Duration::from_millis(config.breaker_cooldown_ms),
+            )),
+            config,
+        }
+    }
+
+    /// Sends a request built by `build`, which gets called once per attempt.
+    ///
+    /// Server errors and `429 Too Many Requests` responses are considered
+    /// failures, but are still returned as is once retries are exhausted.
+    pub async fn send(
+        &self,
+        idempotency: Idempotency,
+        build: impl Fn(&reqwest::Client) -> RequestBuilder,
+    ) -> Result<Response, Error> {
+        let is_failure = |resp: &Response| {
+            resp.status().is_server_error() || resp.status() == StatusCode::TOO_MANY_REQUESTS
+        };
+
+        self.execute(
+            idempotency,
+            || build(&self.http).send().map_err(Into::into),
+            is_failure,
+        )
+        .await
+    }
+
+    /// Makes a call via a third-party client, considering every error a
+    /// failure.
+    pub async fn call<T, E, F>(
+        &self,
+        idempotency: Idempotency,
+        f: impl Fn() -> F,
+    ) -> Result<T, Error>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: Into<anyhow::Error>,
+    {
+        self.execute(idempotency, || f().map_err(Into::into), |_| false)
+            .await
+    }
+
+    async fn execute<T, F>(
+        &self,
+        idempotency: Idempotency,
+        f: impl Fn() -> F,
+        is_failure: impl Fn(&T) -> bool,
+    ) -> Result<T, Error>
+    where
+        F: Future<Output = anyhow::Result<T>>,
+    {
+        let max_attempts = match idempotency {
+            Idempotency::Idempotent => self.config.max_retries + 1,
+            Idempotency::NonIdempotent => 1,
+        };
+        let timeout = Duration::from_millis(self.config.timeout_ms);
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+
+            if !self.breaker.try_acquire() {
+                self.record_metrics("circuit_open", None);
+                return Err(Error::CircuitOpen(self.upstream));
+            }
+
+            let started_at = Instant::now();
+            let (result, outcome) = match tokio::time::timeout(timeout, f()).await {
+                Ok(Ok(value)) if is_failure(&value) => (Ok(value), "failure"),
+                Ok(Ok(value)) => (Ok(value), "success"),
+                Ok(Err(e)) => (Err(Error::Other(e)), "error"),
+                Err(_) => (Err(Error::Timeout(self.upstream)), "timeout"),
+            };
+
+            let success = outcome == "success";
+            self.breaker.record(success);
+            self.record_metrics(outcome, Some(started_at.elapsed()));
+
+            if success || attempt >= max_attempts {
+                return result;
+            }
+
+            warn!(
+                upstream = self.upstream,
+                attempt, outcome, "Retrying outbound call"
+            );
+            counter!("outbound_http_retries", 1, "upstream" => self.upstream);
+            tokio::time::sleep(self.backoff(attempt)).await;
+        }
+    }
+
+    /// Exponential backoff with full jitter.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let max = self
+            .config
+            .retry_base_delay_ms
+            .saturating_mul(1 << (attempt - 1).min(16));
+
+        Duration::from_millis(rand::thread_rng().gen_range(0..=max))
+    }
+
+    fn record_metrics(&self, outcome: &'static str, latency: Option<Duration>) {
+        counter!("outbound_http_requests", 1, "upstream" => self.upstream, "outcome" => outcome);
+
+        if let Some(latency) = latency {
+            histogram!("outbound_http_latency", latency.as_secs_f64(), "upstream" => self.upstream);
+        }
+
+        gauge!(
+            "outbound_http_circuit_breaker_state",
+            self.breaker.state().as_f64(),
+            "upstream" => self.upstream
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_client_retries() {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    let client = Client::new("test", Config {
+        retry_base_delay_ms: 0,
+        breaker_failure_threshold: 0,
+        ..Default::default()
+    });
+
+    let calls = AtomicU32::new(0);
+    let flaky = || async {
+        match calls.fetch_add(1, Ordering::Relaxed) {
+            0 | 1 => Err(anyhow::anyhow!("flaky")),
+            n => Ok(n),
+        }
+    };
+
+    assert_eq!(
+        client.call(Idempotency::Idempotent, flaky).await.unwrap(),
+        2
+    );
+
+    calls.store(0, Ordering::Relaxed);
+    assert!(client
+        .call(Idempotency::NonIdempotent, flaky)
+        .await
+        .is_err());
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn test_client_circuit_breaker() {
+    let client = Client::new("test", Config {
+        max_retries: 0,
+        breaker_failure_threshold: 1,
+        ..Default::default()
+    });
+
+    let failing = || async { Err::<(), _>(anyhow::anyhow!("down")) };
+    assert!(matches!(
+        client.call(Idempotency::Idempotent, failing).await,
+        Err(Error::Other(_))
+    ));
+    assert!(matches!(
+        client.call(Idempotency::Idempotent, failing).await,
+        Err(Error::CircuitOpen("test"))
+    ));
+}
+
+#[tokio::test]
+async fn test_client_timeout() {
+    let client = Client::new("test", Config {
+        timeout_ms: 10,
+        max_retries: 0,
+        ..Default::default()
+    });
+
+    let slow = || async {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        Ok::<_, anyhow::Error>(())
+    };
+    assert!(matches!(
+        client.call(Idempotency::Idempotent, slow).await,
+        Err(Error::Timeout("test"))
+    ));
+}
+
+#[test]
+fn test_config_max_duration() {
+    assert_eq!(
+        Config::default().max_duration(),
+        Duration::from_millis(1000 * 3 + 50 + 100)
+    );
+}
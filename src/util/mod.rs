@@ -1 +1,7 @@
+pub mod circuit_breaker;
+pub mod geoip;
+pub mod http;
 pub mod redis;
+pub mod signer;
+pub mod time;
+pub mod watch;
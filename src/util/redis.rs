@@ -1,12 +1,13 @@
 use {
     anyhow::Context as _,
     deadpool_redis::{
-        redis::{AsyncCommands as _, FromRedisValue, ToRedisArgs},
+        redis::{self, AsyncCommands as _, FromRedisValue, ToRedisArgs},
         Connection,
         Pool,
         Runtime,
     },
     metrics::counter,
+    std::time::Duration,
     tap::TapFallible,
 };
 
@@ -70,4 +71,75 @@ impl Adapter {
             .tap_ok(|_| self.incr_counter("redis_reads"))
             .tap_err(|_| self.incr_counter("redis_read_errors"))
     }
+
+    pub async fn set<K, V>(&self, key: K, value: V) -> Result<()>
+    where
+        K: ToRedisArgs + Send + Sync,
+        V: ToRedisArgs + Send + Sync,
+    {
+        self.get_conn()
+            .await?
+            .set(key, value)
+            .await
+            .context("SET operation failed")
+            .tap_ok(|_| self.incr_counter("redis_writes"))
+            .tap_err(|_| self.incr_counter("redis_write_errors"))
+    }
+
+    /// Iterates over the keys of the database, see `SCAN`.
+    pub async fn scan(&self, cursor: u64, count: usize) -> Result<(u64, Vec<String>)> {
+        redis::cmd("SCAN")
+            .arg(cursor)
+            .arg("COUNT")
+            .arg(count)
+            .query_async(&mut self.get_conn().await?)
+            .await
+            .context("SCAN operation failed")
+            .tap_ok(|_| self.incr_counter("redis_reads"))
+            .tap_err(|_| self.incr_counter("redis_read_errors"))
+    }
+
+    /// Runs a Lua script on the `keys`, see `EVAL`.
+    pub async fn eval<K, A, V>(&self, script: &str, keys: &[K], args: A) -> Result<V>
+    where
+        K: ToRedisArgs + Send + Sync,
+        A: ToRedisArgs + Send + Sync,
+        V: FromRedisValue,
+    {
+        redis::cmd("EVAL")
+            .arg(script)
+            .arg(keys.len())
+            .arg(keys)
+            .arg(args)
+            .query_async(&mut self.get_conn().await?)
+            .await
+            .context("EVAL operation failed")
+            .tap_ok(|_| self.incr_counter("redis_writes"))
+            .tap_err(|_| self.incr_counter("redis_write_errors"))
+    }
+
+    /// Returns the value of a key along with its remaining time to live, both
+    /// read in a single transaction, see `GET` and `PTTL`.
+    ///
+    /// The time to live is `None` if the key has no expiration.
+    pub async fn get_with_ttl<K, V>(&self, key: K) -> Result<Option<(V, Option<Duration>)>>
+    where
+        K: ToRedisArgs + Send + Sync,
+        V: FromRedisValue,
+    {
+        let (value, millis): (Option<V>, i64) = redis::pipe()
+            .atomic()
+            .get(&key)
+            .cmd("PTTL")
+            .arg(&key)
+            .query_async(&mut self.get_conn().await?)
+            .await
+            .context("GET and PTTL transaction failed")
+            .tap_ok(|_| self.incr_counter("redis_reads"))
+            .tap_err(|_| self.incr_counter("redis_read_errors"))?;
+
+        // PTTL is `-1` for a key without expiration, while a missing key (`-2`)
+        // has no value either.
+        Ok(value.map(|value| (value, u64::try_from(millis).ok().map(Duration::from_millis))))
+    }
 }
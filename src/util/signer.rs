@@ -0,0 +1,155 @@
+use {
+    base64::{engine::general_purpose::STANDARD as BASE64, Engine as _},
+    hmac::{Hmac, Mac},
+    hyper::{
+        http::{HeaderName, HeaderValue},
+        HeaderMap,
+        Method,
+    },
+    sha2::{Digest as _, Sha256},
+    std::time::Duration,
+};
+
+/// Header containing the unix timestamp (in seconds) of a signed request.
+pub const TIMESTAMP_HEADER: &str = "x-bouncer-timestamp";
+
+/// Header containing the base64 encoded HMAC-SHA256 signature of a request.
+pub const SIGNATURE_HEADER: &str = "x-bouncer-signature";
+
+/// Maximum difference between the timestamp of a signed request and the
+/// current time for the request to be accepted.
+pub const MAX_CLOCK_SKEW: Duration = Duration::from_secs(60);
+
+/// Authenticates requests exchanged with a peer (e.g. the Cloudflare KV worker
+/// or the project registry webhooks) using a secret shared only with it.
+///
+/// The signature covers the method, the path, the timestamp and the SHA-256
+/// hash of the body of a request:
+/// `HMAC-SHA256(secret, "{method}\n{path}\n{timestamp}\n{hex(sha256(body))}")`.
+#[derive(Clone)]
+pub struct RequestSigner {
+    mac: Hmac<Sha256>,
+}
+
+/// Error of verifying a signed request via [`RequestSigner::verify`].
+#[derive(Debug, thiserror::Error, Eq, PartialEq)]
+pub enum VerifySignatureError {
+    #[error("Missing or malformed signature headers")]
+    MalformedHeaders,
+
+    #[error("Request timestamp is too far from the current time")]
+    Expired,
+
+    #[error("Invalid signature")]
+    InvalidSignature,
+}
+
+impl RequestSigner {
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            mac: Hmac::new_from_slice(secret).expect("HMAC accepts keys of any size"),
+        }
+    }
+
+    fn mac(&self, method: &Method, path: &str, body: &[u8], timestamp: u64) -> Hmac<Sha256> {
+        let body_hash: String = Sha256::digest(body)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+
+        let mut mac = self.mac.clone();
+        mac.update(format!("{method}\n{path}\n{timestamp}\n{body_hash}").as_bytes());
+        mac
+    }
+
+    /// Builds the headers authenticating a request.
+    pub fn sign(&self, method: &Method, path: &str, body: &[u8], timestamp: u64) -> HeaderMap {
+        let signature = self.mac(method, path, body, timestamp).finalize();
+
+        HeaderMap::from_iter([
+            (
+                HeaderName::from_static(TIMESTAMP_HEADER),
+                HeaderValue::from(timestamp),
+            ),
+            (
+                HeaderName::from_static(SIGNATURE_HEADER),
+                // base64 alphabet is a valid header value.
+                HeaderValue::from_str(&BASE64.encode(signature.into_bytes())).unwrap(),
+            ),
+        ])
+    }
+
+    /// Verifies the headers built by [`RequestSigner::sign`], for use by
+    /// stand-ins of the worker.
+    pub fn verify(
+        &self,
+        method: &Method,
+        path: &str,
+        body: &[u8],
+        headers: &HeaderMap,
+        now: u64,
+    ) -> std::result::Result<(), VerifySignatureError> {
+        let header = |name| headers.get(name)?.to_str().ok();
+
+        let timestamp: u64 = header(TIMESTAMP_HEADER)
+            .and_then(|s| s.parse().ok())
+            .ok_or(VerifySignatureError::MalformedHeaders)?;
+        let signature = header(SIGNATURE_HEADER)
+            .and_then(|s| BASE64.decode(s).ok())
+            .ok_or(VerifySignatureError::MalformedHeaders)?;
+
+        if timestamp.abs_diff(now) > MAX_CLOCK_SKEW.as_secs() {
+            return Err(VerifySignatureError::Expired);
+        }
+
+        self.mac(method, path, body, timestamp)
+            .verify_slice(&signature)
+            .map_err(|_| VerifySignatureError::InvalidSignature)
+    }
+}
+
+#[test]
+fn test_request_signer() {
+    let signer = RequestSigner::new(b"secret");
+    let body = br#"{"attestationId":"a","origin":"https://walletconnect.com"}"#;
+    let headers = signer.sign(&Method::POST, "/attestation", body, 1000);
+
+    let verify = |signer: &RequestSigner, method, path, body: &[u8], now| {
+        signer.verify(&method, path, body, &headers, now)
+    };
+
+    assert_eq!(
+        verify(&signer, Method::POST, "/attestation", body, 1030),
+        Ok(())
+    );
+    assert_eq!(
+        verify(&signer, Method::POST, "/attestation", b"{}", 1000),
+        Err(VerifySignatureError::InvalidSignature)
+    );
+    assert_eq!(
+        verify(&signer, Method::PUT, "/attestation", body, 1000),
+        Err(VerifySignatureError::InvalidSignature)
+    );
+    assert_eq!(
+        verify(&signer, Method::POST, "/other", body, 1000),
+        Err(VerifySignatureError::InvalidSignature)
+    );
+    assert_eq!(
+        verify(
+            &RequestSigner::new(b"other"),
+            Method::POST,
+            "/attestation",
+            body,
+            1000
+        ),
+        Err(VerifySignatureError::InvalidSignature)
+    );
+    assert_eq!(
+        verify(&signer, Method::POST, "/attestation", body, 1061),
+        Err(VerifySignatureError::Expired)
+    );
+    assert_eq!(
+        signer.verify(&Method::POST, "/attestation", body, &HeaderMap::new(), 1000),
+        Err(VerifySignatureError::MalformedHeaders)
+    );
+}
@@ -0,0 +1,33 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// Periodically calls `reload` whenever the modification time of the file at
+/// `path` changes.
+///
+/// A failed reload is retried on the next tick, so the callers keep their
+/// current state until the file becomes valid again.
+pub async fn watch_file(
+    path: PathBuf,
+    interval: Duration,
+    mut reload: impl FnMut(&Path) -> anyhow::Result<()>,
+) {
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+
+    let mut last_modified: Option<SystemTime> = modified(&path);
+    let mut interval = tokio::time::interval(interval);
+
+    loop {
+        interval.tick().await;
+
+        let current = modified(&path);
+        if current == last_modified {
+            continue;
+        }
+
+        if reload(&path).is_ok() {
+            last_modified = current;
+        }
+    }
+}
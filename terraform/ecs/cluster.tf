@@ -87,6 +87,7 @@ resource "aws_ecs_task_definition" "app_task" {
         { name = "SCAM_GUARD_CACHE_URL", value = var.scam_guard_cache_url },
 
         { name = "CF_KV_ENDPOINT", value = var.cf_kv_endpoint },
+        { name = "CF_KV_SECRET", value = var.cf_kv_secret },
 
         { name = "DATA_LAKE_BUCKET", value = var.analytics_datalake_bucket_name },
 
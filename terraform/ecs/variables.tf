@@ -157,6 +157,12 @@ variable "cf_kv_endpoint" {
   type        = string
 }
 
+variable "cf_kv_secret" {
+  description = "The secret used for signing requests to the Cloudflare KV worker"
+  type        = string
+  sensitive   = true
+}
+
 variable "ofac_blocked_countries" {
   description = "The list of countries under OFAC sanctions"
   type        = string
@@ -66,6 +66,7 @@ module "ecs" {
   scam_guard_cache_url       = "redis://${module.redis.endpoint}/2"
 
   cf_kv_endpoint = var.cf_kv_endpoint
+  cf_kv_secret   = var.cf_kv_secret
 
   ofac_blocked_countries = var.ofac_blocked_countries
 
@@ -83,6 +83,12 @@ variable "cf_kv_endpoint" {
   type        = string
 }
 
+variable "cf_kv_secret" {
+  description = "The secret used for signing requests to the Cloudflare KV worker"
+  type        = string
+  sensitive   = true
+}
+
 #-------------------------------------------------------------------------------
 # Project Registry
 